@@ -0,0 +1,50 @@
+use iced_macroquad::{iced, Program};
+
+use iced::alignment::Horizontal;
+use iced::widget::{button, center, column, text};
+use iced::{Element, Task, Theme};
+
+#[derive(Debug, Clone)]
+enum Message {
+    Add,
+    Sub,
+    Copy,
+}
+
+#[derive(Default)]
+struct Counter {
+    value: i32,
+}
+
+impl Program for Counter {
+    type Message = Message;
+    type Theme = Theme;
+
+    fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Add => self.value += 1,
+            Message::Sub => self.value -= 1,
+            Message::Copy => return iced::clipboard::write(self.value.to_string()),
+        }
+
+        Task::none()
+    }
+
+    fn view(&self) -> Element<'_, Message, Theme> {
+        center(
+            column![
+                button("Add +").on_press(Message::Add),
+                text(self.value),
+                button("Sub -").on_press(Message::Sub),
+                button("Copy").on_press(Message::Copy),
+            ]
+            .align_x(Horizontal::Center),
+        )
+        .into()
+    }
+}
+
+#[macroquad::main("macroquad with iced")]
+async fn main() {
+    iced_macroquad::run(Counter::default()).await
+}
@@ -0,0 +1,125 @@
+//! Choose where copied text goes.
+//!
+//! Any [`Clipboard`] can back the UI, set it for every interface with [`set_clipboard`] or for a
+//! single one with [`Interface::set_clipboard`](crate::Interface::set_clipboard).
+
+pub use iced_core::clipboard::Kind;
+pub use iced_core::Clipboard;
+
+use crate::context::global;
+use crate::mq::window::{clipboard_get, clipboard_set};
+
+/// Use `clipboard` for every interface that doesn't have its own.
+pub fn set_clipboard(clipboard: impl Clipboard + 'static) {
+    global::iced_ctx_mut(|ctx| ctx.clipboard = Box::new(clipboard));
+}
+
+/// The clipboard of the system, the default.
+///
+/// Only the standard clipboard is supported, there's no primary selection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct System;
+
+impl Clipboard for System {
+    fn read(&self, kind: Kind) -> Option<String> {
+        match kind {
+            Kind::Standard => clipboard_get(),
+            Kind::Primary => None,
+        }
+    }
+
+    fn write(&mut self, kind: Kind, contents: String) {
+        match kind {
+            Kind::Standard => clipboard_set(&contents),
+            Kind::Primary => (),
+        }
+    }
+}
+
+/// A clipboard that only lives in memory, behaving the same on every platform.
+///
+/// Useful for tests, or to keep copied text within the game.
+#[derive(Debug, Clone, Default)]
+pub struct Memory {
+    standard: Option<String>,
+    primary: Option<String>,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for Memory {
+    fn read(&self, kind: Kind) -> Option<String> {
+        match kind {
+            Kind::Standard => self.standard.clone(),
+            Kind::Primary => self.primary.clone(),
+        }
+    }
+
+    fn write(&mut self, kind: Kind, contents: String) {
+        match kind {
+            Kind::Standard => self.standard = Some(contents),
+            Kind::Primary => self.primary = Some(contents),
+        }
+    }
+}
+
+/// Adds a primary selection to a clipboard that only has a standard one.
+///
+/// Selected text can then be pasted elsewhere in the game, as with the primary selection on
+/// Linux. The primary selection isn't shared with other applications.
+#[derive(Debug, Clone, Default)]
+pub struct WithPrimary<C = System> {
+    standard: C,
+    primary: Option<String>,
+}
+
+impl<C> WithPrimary<C> {
+    pub fn new(standard: C) -> Self {
+        Self {
+            standard,
+            primary: None,
+        }
+    }
+}
+
+impl<C: Clipboard> Clipboard for WithPrimary<C> {
+    fn read(&self, kind: Kind) -> Option<String> {
+        match kind {
+            Kind::Standard => self.standard.read(kind),
+            Kind::Primary => self.primary.clone(),
+        }
+    }
+
+    fn write(&mut self, kind: Kind, contents: String) {
+        match kind {
+            Kind::Standard => self.standard.write(kind, contents),
+            Kind::Primary => self.primary = Some(contents),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primary_selection_is_kept_apart() {
+        let mut clipboard = WithPrimary::new(Memory {
+            primary: Some("unused".into()),
+            ..Memory::new()
+        });
+        clipboard.write(Kind::Standard, "copied".into());
+        clipboard.write(Kind::Primary, "selected".into());
+
+        assert_eq!(clipboard.read(Kind::Standard).as_deref(), Some("copied"));
+        assert_eq!(clipboard.read(Kind::Primary).as_deref(), Some("selected"));
+        assert_eq!(
+            clipboard.standard.read(Kind::Primary).as_deref(),
+            Some("unused")
+        );
+    }
+}
@@ -1,47 +1,212 @@
-use std::sync::OnceLock;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-use iced_core::{Font, Pixels, Size};
+use iced_core::{window, Event, Font, Pixels, Size};
 use iced_graphics::Viewport;
 use iced_tiny_skia;
 
-use crate::event_handler::{EventProxy, EventProxyWrapper};
+use crate::clipboard::{self, Clipboard};
+use crate::event_handler::{EventProxy, EventProxyWrapper, KeyboardState};
+use crate::input::Capture;
 
-use crate::mq::window::{clipboard_get, clipboard_set, dpi_scale, screen_size, set_mouse_cursor};
+use crate::mq::window::{dpi_scale, screen_size, set_mouse_cursor};
 use crate::mq::CursorIcon;
 
+use crate::macroquad::experimental::coroutines::{start_coroutine, Coroutine};
 use crate::macroquad::input::mouse_position;
+use crate::macroquad::window::next_frame;
 
-pub(crate) static DEFAULTS: OnceLock<(Font, Pixels)> = OnceLock::new();
+static DEFAULTS: Mutex<(Font, Pixels)> = Mutex::new((Font::DEFAULT, Pixels(24.0)));
+/// Bumped whenever the defaults or fonts change, so renderers and cached interfaces get rebuilt.
+static DEFAULTS_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn set_defaults(font: Font, text_size: Pixels) {
+    *DEFAULTS.lock().unwrap_or_else(|e| e.into_inner()) = (font, text_size);
+    invalidate_renderers();
+}
+
+/// Rebuild renderers and interfaces, e.g. after fonts were reordered.
+pub(crate) fn invalidate_renderers() {
+    DEFAULTS_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counted by a coroutine, as macroquad only counts frames in its immediate mode UI.
+static FRAME: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static FRAME_COUNTER: Cell<Option<Coroutine>> = const { Cell::new(None) };
+    /// macroquad can't unsubscribe from input, so one subscription is shared by every context.
+    static INPUT_SUBSCRIBER: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// The number of the current frame.
+///
+/// Unlike `root_ui().frame` it doesn't borrow macroquad's UI, so it's fine to call while a
+/// macroquad window is being built.
+pub(crate) fn frame() -> u64 {
+    FRAME_COUNTER.with(|counter| {
+        // Start counting on first use, and again if the game stopped every coroutine.
+        let running = counter.get().is_some_and(|counter| !counter.is_done());
+        if !running {
+            counter.set(Some(start_coroutine(async {
+                loop {
+                    FRAME.fetch_add(1, Ordering::Relaxed);
+                    next_frame().await;
+                }
+            })));
+        }
+    });
+
+    FRAME.load(Ordering::Relaxed)
+}
+
+fn input_subscriber() -> usize {
+    INPUT_SUBSCRIBER.with(|subscriber| {
+        let id = subscriber
+            .get()
+            .unwrap_or_else(macroquad::input::utils::register_input_subscriber);
+        subscriber.set(Some(id));
+        id
+    })
+}
 
 pub(crate) struct Context {
     pub renderer: crate::iced::Renderer,
-    pub compositor: renderer::Compositor,
-    pub clipboard: Clipboard,
+    pub clipboard: Box<dyn Clipboard>,
     pub input_subscriber_id: usize,
+    pub keyboard: KeyboardState,
+    pub capture: Capture,
+    pub scale_factor: f64,
+    events: FrameEvents,
+    defaults_generation: usize,
+}
+
+/// The input of a frame, shared by every interface viewed during it.
+#[derive(Default)]
+struct FrameEvents {
+    frame: Option<u64>,
+    events: Vec<Event>,
+    /// Events handled by an interface, which aren't delivered to the ones after it.
+    captured: Vec<bool>,
+}
+
+impl FrameEvents {
+    fn start(&mut self, frame: u64, events: Vec<Event>) {
+        self.captured = vec![false; events.len()];
+        self.events = events;
+        self.frame = Some(frame);
+    }
+
+    /// Add the events of `frame` that no interface captured yet to `events`.
+    ///
+    /// Updating an interface again in the same frame gives it nothing rather than the same
+    /// presses twice.
+    fn deliver(&self, frame: u64, received: &mut Option<u64>, events: &mut Vec<(usize, Event)>) {
+        if received.replace(frame) == Some(frame) || self.frame != Some(frame) {
+            return;
+        }
+
+        let uncaptured = self.events.iter().enumerate();
+        events.extend(
+            uncaptured
+                .filter(|(id, _)| !self.captured[*id])
+                .map(|(id, event)| (id, event.clone())),
+        );
+    }
+
+    fn capture(&mut self, id: usize) {
+        if let Some(captured) = self.captured.get_mut(id) {
+            *captured = true;
+        }
+    }
+}
+
+pub(crate) fn create_renderer() -> crate::iced::Renderer {
+    let (font, text_size) = *DEFAULTS.lock().unwrap_or_else(|e| e.into_inner());
+    iced_tiny_skia::Renderer::new(font, text_size)
 }
 
 impl Context {
     fn new() -> Self {
-        let (width, height) = screen_size();
-        let (font, text_size) = *DEFAULTS.get_or_init(|| (Font::DEFAULT, Pixels(24.0)));
+        let mut context = Self {
+            input_subscriber_id: input_subscriber(),
+            renderer: create_renderer(),
+            clipboard: Box::new(clipboard::System),
+            keyboard: KeyboardState::default(),
+            capture: Capture::default(),
+            scale_factor: dpi_scale() as f64,
+            events: FrameEvents::default(),
+            defaults_generation: DEFAULTS_GENERATION.load(Ordering::Relaxed),
+        };
+
+        // Input that arrived while there was no context, after a shutdown, is stale.
+        context.read_events(|_| ());
+        context
+    }
+
+    /// Rebuild the renderer if the default font or text size changed.
+    ///
+    /// Returns the generation of the defaults now in use.
+    pub fn update_defaults(&mut self) -> usize {
+        let generation = DEFAULTS_GENERATION.load(Ordering::Relaxed);
 
-        Self {
-            input_subscriber_id: macroquad::input::utils::register_input_subscriber(),
-            renderer: iced_tiny_skia::Renderer::new(font, text_size),
-            compositor: renderer::Compositor::new(Size::new(width as u32, height as u32)),
-            clipboard: Clipboard::default(),
+        if generation != self.defaults_generation {
+            self.defaults_generation = generation;
+            self.renderer = create_renderer();
         }
+
+        generation
     }
 
-    pub fn read_events<T: EventProxy>(&self, event_proxy: T) {
-        macroquad::input::utils::repeat_all_miniquad_input(
-            &mut EventProxyWrapper(event_proxy),
-            self.input_subscriber_id,
-        );
+    /// The events of this frame that no interface captured yet, along with their ids.
+    ///
+    /// Interfaces are given the input in the order they're updated, so the first one is on top.
+    /// `received` is the last frame the interface got events for, see [`FrameEvents::deliver`].
+    pub fn frame_events(&mut self, received: &mut Option<u64>, events: &mut Vec<(usize, Event)>) {
+        let frame = frame();
+
+        if *received != Some(frame) && self.events.frame != Some(frame) {
+            let mut fresh = Vec::new();
+            self.read_events(&mut fresh);
+            self.events.start(frame, fresh);
+        }
+
+        self.events.deliver(frame, received, events);
     }
 
-    pub fn present(&mut self, viewport: &Viewport) {
-        self.compositor.present(&mut self.renderer, &viewport);
+    /// Keep an event from reaching the interfaces updated after this one.
+    pub fn capture_event(&mut self, id: usize) {
+        self.events.capture(id);
+    }
+
+    fn read_events<T: EventProxy>(&mut self, mut event_proxy: T) {
+        if self.update_scale_factor() {
+            let (width, height) = self.screen_size();
+            let scale_factor = self.scale_factor as f32;
+            let size = Size::new(width as f32 / scale_factor, height as f32 / scale_factor);
+            event_proxy.add(Event::Window(window::Event::Resized(size)));
+        }
+
+        let mut proxy =
+            EventProxyWrapper::new(event_proxy, &mut self.keyboard, self.scale_factor as f32);
+        macroquad::input::utils::repeat_all_miniquad_input(&mut proxy, self.input_subscriber_id);
+        proxy.finish();
+    }
+
+    /// Rebuild scale dependent state if the window moved to a display with a different DPI.
+    ///
+    /// Returns true if the scale factor changed.
+    fn update_scale_factor(&mut self) -> bool {
+        let scale_factor = self.dpi_scale();
+
+        if scale_factor == self.scale_factor {
+            return false;
+        }
+
+        self.scale_factor = scale_factor;
+        self.renderer = create_renderer();
+        true
     }
 
     pub fn dpi_scale(&self) -> f64 {
@@ -53,6 +218,7 @@ impl Context {
         (width as u32, height as u32)
     }
 
+    /// The cursor position in logical pixels, macroquad has already applied the DPI scale.
     pub fn mouse_position(&self) -> (f32, f32) {
         mouse_position()
     }
@@ -62,54 +228,122 @@ impl Context {
     }
 
     pub fn viewport(&self) -> Viewport {
-        Viewport::with_physical_size(self.screen_size().into(), self.dpi_scale())
+        Viewport::with_physical_size(self.screen_size().into(), self.scale_factor)
     }
-}
-
-#[derive(Default)]
-pub struct Clipboard;
 
-impl iced_core::Clipboard for Clipboard {
-    fn read(&self, _kind: iced_core::clipboard::Kind) -> Option<String> {
-        clipboard_get()
-    }
+    /// A viewport for a region of the screen, given in logical pixels.
+    pub fn viewport_for(&self, size: Size<f32>) -> Viewport {
+        let scale_factor = self.scale_factor as f32;
+        let physical_size = Size::new(
+            (size.width * scale_factor).round() as u32,
+            (size.height * scale_factor).round() as u32,
+        );
 
-    fn write(&mut self, _kind: iced_core::clipboard::Kind, contents: String) {
-        clipboard_set(&contents)
+        Viewport::with_physical_size(physical_size, self.scale_factor)
     }
 }
 
 pub(crate) mod global {
-    use std::{cell::RefCell, sync::Once};
+    use std::cell::RefCell;
 
     use crate::context::Context;
 
     thread_local! {
-        static ICED_CONTEXT: RefCell<Context> = init_single_thread(|| RefCell::new(Context::new()));
+        static ICED_CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
     }
 
-    fn init_single_thread<T>(init: impl FnOnce() -> T) -> T {
-        try_init_single_thread(init).expect("Already initialized from another thread.")
+    /// Create the UI context of this thread, if it doesn't exist yet.
+    ///
+    /// This happens on first use anyway, but may be done early to pay for loading fonts up
+    /// front. Each thread has a context of its own, though macroquad only runs on one of them.
+    pub fn init() {
+        init_with(Context::new)
     }
 
-    fn try_init_single_thread<T>(init: impl FnOnce() -> T) -> Option<T> {
-        static ONCE: Once = Once::new();
-        let mut obj = None;
-        ONCE.call_once(|| obj = Some(init()));
-        obj
+    fn init_with(create: impl FnOnce() -> Context) {
+        ICED_CONTEXT.with_borrow_mut(|context| {
+            if context.is_none() {
+                *context = Some(create());
+            }
+        })
+    }
+
+    /// Drop the UI context of this thread, so the next use creates a fresh one.
+    ///
+    /// Call it when restarting the render loop, and drop every [`Interface`](crate::Interface)
+    /// too. Input that arrives while there is no context is discarded.
+    pub fn shutdown() {
+        ICED_CONTEXT.with_borrow_mut(Option::take);
     }
 
     pub fn iced_ctx_mut<T>(f: impl FnOnce(&mut Context) -> T) -> T {
-        ICED_CONTEXT.with_borrow_mut(f)
+        init();
+        ICED_CONTEXT.with_borrow_mut(|context| f(context.as_mut().expect("UI context")))
+    }
+
+    /// Like [`iced_ctx_mut`], but doesn't create the context if there is none.
+    pub fn try_iced_ctx_mut<T>(f: impl FnOnce(&mut Context) -> T) -> Option<T> {
+        ICED_CONTEXT.with_borrow_mut(|context| context.as_mut().map(f))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use crate::clipboard::Memory;
+        use crate::context::{create_renderer, Capture, FrameEvents, KeyboardState};
+
+        /// A context that doesn't need macroquad to be running.
+        fn headless() -> Context {
+            Context {
+                renderer: create_renderer(),
+                clipboard: Box::new(Memory::new()),
+                input_subscriber_id: 0,
+                keyboard: KeyboardState::default(),
+                capture: Capture::default(),
+                scale_factor: 1.0,
+                events: FrameEvents::default(),
+                defaults_generation: 0,
+            }
+        }
+
+        #[test]
+        fn every_thread_has_a_context_of_its_own() {
+            let threads: Vec<_> = (0..2)
+                .map(|_| {
+                    std::thread::spawn(|| {
+                        init_with(headless);
+                        assert!(try_iced_ctx_mut(|_| ()).is_some());
+
+                        shutdown();
+                        assert!(try_iced_ctx_mut(|_| ()).is_none());
+
+                        // Exiting without a shutdown leaves nothing behind for other threads.
+                        init_with(headless);
+                    })
+                })
+                .collect();
+
+            for thread in threads {
+                thread.join().expect("Context on its own thread");
+            }
+
+            init_with(headless);
+            assert!(try_iced_ctx_mut(|_| ()).is_some());
+            shutdown();
+        }
     }
 }
 
-mod renderer {
+pub(crate) mod renderer {
     use crate::iced::Renderer;
-    use iced_core::{Color, Rectangle, Size};
+    use iced_core::{Color, Point, Rectangle, Size};
     use iced_graphics::{damage, Viewport};
     use iced_tiny_skia::Layer;
-    use macroquad::texture::Texture2D;
+    use macroquad::camera::Camera;
+    use macroquad::math::Mat4;
+    use macroquad::prelude::RenderPass;
+    use macroquad::texture::{Image, Texture2D};
 
     pub fn create_texture(size: Size<u32>, buffer: &[u32]) -> Texture2D {
         let texture = Texture2D::from_rgba8(
@@ -121,11 +355,19 @@ mod renderer {
         texture
     }
 
+    /// Upload the whole surface once damage covers this fraction of it.
+    const FULL_UPLOAD_THRESHOLD: f32 = 0.5;
+
     pub struct Surface {
         dirty: bool,
+        /// Regions, in physical pixels, that changed since the last upload.
+        damage: Vec<Rectangle<u32>>,
         buffer: Vec<u32>,
-        texture: Texture2D,
+        /// Created on first use, so the surface can be rendered without a window.
+        texture: Option<Texture2D>,
         size: Size<u32>,
+        /// Reused to stage partial uploads.
+        staging: Image,
     }
 
     impl Surface {
@@ -133,14 +375,35 @@ mod renderer {
             let buffer = vec![0; (size.width * size.height) as usize];
             Self {
                 dirty: true,
+                damage: Vec::new(),
                 size,
-                texture: create_texture(size, &buffer),
+                texture: None,
                 buffer,
+                staging: Image::empty(),
             }
         }
 
-        pub fn data_mut(&mut self) -> &mut [u8] {
-            self.dirty = true;
+        /// Only the `damage`d regions, given in logical pixels, will be uploaded.
+        pub fn data_mut(&mut self, damage: &[Rectangle], scale_factor: f32) -> &mut [u8] {
+            let bounds =
+                Rectangle::with_size(Size::new(self.size.width as f32, self.size.height as f32));
+
+            // Round outwards, so pixels partially covered by a region are uploaded too.
+            let physical = damage.iter().filter_map(|region| {
+                let region = *region * scale_factor;
+                let x = region.x.floor();
+                let y = region.y.floor();
+                let region = Rectangle {
+                    x,
+                    y,
+                    width: (region.x + region.width).ceil() - x,
+                    height: (region.y + region.height).ceil() - y,
+                };
+
+                region.intersection(&bounds)?.snap()
+            });
+
+            self.damage.extend(physical);
             bytemuck::cast_slice_mut(&mut self.buffer)
         }
 
@@ -148,20 +411,147 @@ mod renderer {
             bytemuck::cast_slice(&self.buffer)
         }
 
+        /// Copy the surface into an image with straight alpha.
+        pub fn to_image(&self) -> Image {
+            let mut bytes = self.data().to_vec();
+
+            for pixel in bytes.chunks_exact_mut(4) {
+                let alpha = pixel[3] as u16;
+                if alpha != 0 && alpha != 255 {
+                    for channel in &mut pixel[..3] {
+                        *channel = ((*channel as u16 * 255 + alpha / 2) / alpha) as u8;
+                    }
+                }
+            }
+
+            Image {
+                bytes,
+                width: self.size.width as u16,
+                height: self.size.height as u16,
+            }
+        }
+
+        /// Blend the surface over `image`, with its top left corner at `x` and `y`.
+        pub fn blend_onto(&self, image: &mut Image, x: i32, y: i32) {
+            let (width, height) = (image.width as i32, image.height as i32);
+
+            for row in y.max(0)..(y + self.size.height as i32).min(height) {
+                for column in x.max(0)..(x + self.size.width as i32).min(width) {
+                    let source =
+                        (row - y) as usize * self.size.width as usize + (column - x) as usize;
+                    let source: [u8; 4] = bytemuck::cast(self.buffer[source]);
+                    let target = (row * width + column) as usize * 4;
+                    let target = &mut image.bytes[target..target + 4];
+
+                    // The surface is premultiplied, so it's only the destination that's scaled.
+                    let remaining = 255 - source[3] as u16;
+                    for (target, source) in target.iter_mut().zip(source) {
+                        *target = source + ((*target as u16 * remaining + 127) / 255) as u8;
+                    }
+                }
+            }
+        }
+
         pub fn resize(&mut self, size: Size<u32>) {
             self.size = size;
             self.buffer
                 .resize(size.width as usize * size.height as usize, 0);
-            self.texture = create_texture(size, &self.buffer);
+            self.texture = None;
             self.dirty = true;
         }
 
-        pub fn update_texture(&mut self) {
+        pub fn update_texture(&mut self) -> &Texture2D {
+            let Some(texture) = &self.texture else {
+                self.damage.clear();
+                self.dirty = false;
+                return self.texture.insert(create_texture(self.size, &self.buffer));
+            };
+
+            let damaged_area: u32 = self.damage.iter().map(|r| r.width * r.height).sum();
+            let area = self.size.width * self.size.height;
+
+            if damaged_area as f32 >= area as f32 * FULL_UPLOAD_THRESHOLD {
+                self.dirty = true;
+            }
+
             if self.dirty {
-                self.texture
-                    .update_from_bytes(self.size.width, self.size.height, self.data());
+                texture.update_from_bytes(self.size.width, self.size.height, self.data());
                 self.dirty = false
+            } else {
+                let damage = std::mem::take(&mut self.damage);
+                for region in &damage {
+                    self.upload_region(*region);
+                }
+                self.damage = damage;
             }
+
+            self.damage.clear();
+            self.texture.as_ref().expect("Surface texture")
+        }
+
+        fn upload_region(&mut self, region: Rectangle<u32>) {
+            let row_start = region.x as usize;
+            let row_end = row_start + region.width as usize;
+
+            self.staging.width = region.width as u16;
+            self.staging.height = region.height as u16;
+            self.staging.bytes.clear();
+
+            for row in region.y..region.y + region.height {
+                let offset = row as usize * self.size.width as usize;
+                let pixels = &self.buffer[offset + row_start..offset + row_end];
+                self.staging
+                    .bytes
+                    .extend_from_slice(bytemuck::cast_slice(pixels));
+            }
+
+            let texture = self.texture.as_ref().expect("Surface texture");
+            texture.update_part(
+                &self.staging,
+                region.x as i32,
+                region.y as i32,
+                region.width as i32,
+                region.height as i32,
+            );
+        }
+
+        /// Copy the buffer into a texture of the same size.
+        pub fn upload_to(&mut self, texture: &Texture2D) {
+            texture.update_from_bytes(self.size.width, self.size.height, self.data());
+
+            // Our own texture has fallen behind, and the damage no longer describes it.
+            self.damage.clear();
+            self.dirty = true;
+        }
+    }
+
+    /// Maps logical pixels onto the screen, like macroquad's default camera.
+    pub struct ScreenCamera {
+        size: Size<f32>,
+    }
+
+    impl ScreenCamera {
+        /// `size` is the logical size of the screen.
+        pub fn new(size: Size<f32>) -> Self {
+            Self { size }
+        }
+    }
+
+    impl Camera for ScreenCamera {
+        fn matrix(&self) -> Mat4 {
+            Mat4::orthographic_rh_gl(0.0, self.size.width, self.size.height, 0.0, -1.0, 1.0)
+        }
+
+        fn depth_enabled(&self) -> bool {
+            false
+        }
+
+        fn render_pass(&self) -> Option<RenderPass> {
+            None
+        }
+
+        fn viewport(&self) -> Option<(i32, i32, i32, i32)> {
+            None
         }
     }
 
@@ -169,6 +559,7 @@ mod renderer {
         mask: tiny_skia::Mask,
         old_frame: Option<Vec<Layer>>,
         surface: Surface,
+        scale_factor: f64,
     }
 
     impl Compositor {
@@ -177,15 +568,27 @@ mod renderer {
                 mask: tiny_skia::Mask::new(size.width, size.height).unwrap(),
                 old_frame: None,
                 surface: Surface::new(size),
+                scale_factor: 1.0,
             }
         }
 
-        pub fn maybe_resize_buffers(&mut self, new_size: Size<u32>) {
-            if self.surface.size != new_size {
-                self.resize(new_size)
+        pub fn maybe_resize_buffers(&mut self, viewport: &Viewport) {
+            if self.surface.size != viewport.physical_size() {
+                self.resize(viewport.physical_size())
+            }
+
+            // Layers are in logical pixels, so they can't tell us if the scale changed.
+            if self.scale_factor != viewport.scale_factor() {
+                self.scale_factor = viewport.scale_factor();
+                self.old_frame = None;
             }
         }
 
+        /// Forget the last frame, so the next one is rendered in full.
+        pub fn invalidate(&mut self) {
+            self.old_frame = None;
+        }
+
         pub fn resize(&mut self, new_size: Size<u32>) {
             self.surface.resize(new_size);
             self.mask = tiny_skia::Mask::new(new_size.width, new_size.height).expect("create mask");
@@ -217,8 +620,9 @@ mod renderer {
             let damage = damage::group(damage, Rectangle::with_size(viewport.logical_size()));
             let physical_size = viewport.physical_size();
 
+            let scale_factor = viewport.scale_factor() as f32;
             let mut pixels = tiny_skia::PixmapMut::from_bytes(
-                self.surface.data_mut(),
+                self.surface.data_mut(&damage, scale_factor),
                 physical_size.width,
                 physical_size.height,
             )
@@ -234,21 +638,175 @@ mod renderer {
             );
         }
 
-        pub fn draw_texture(&mut self) {
-            self.surface.update_texture();
+        pub fn draw_texture(&mut self, position: Point, logical_size: Size<f32>) {
+            let texture = self.surface.update_texture();
 
-            macroquad::texture::draw_texture(
-                &self.surface.texture,
-                0.0,
-                0.0,
+            // The game may have left a camera active, the UI is always drawn in screen space.
+            let screen = Size::new(
+                macroquad::window::screen_width(),
+                macroquad::window::screen_height(),
+            );
+            macroquad::camera::push_camera_state();
+            macroquad::camera::set_camera(&ScreenCamera::new(screen));
+
+            // tiny-skia produces premultiplied alpha, which needs its own blending.
+            macroquad::material::gl_use_material(&crate::material::premultiplied_alpha());
+
+            // The surface is in physical pixels, but macroquad draws in logical pixels.
+            macroquad::texture::draw_texture_ex(
+                texture,
+                position.x,
+                position.y,
                 macroquad::color::WHITE,
+                macroquad::texture::DrawTextureParams {
+                    dest_size: Some(macroquad::math::vec2(
+                        logical_size.width,
+                        logical_size.height,
+                    )),
+                    ..Default::default()
+                },
             );
+
+            macroquad::material::gl_use_default_material();
+            macroquad::camera::pop_camera_state();
         }
 
-        pub fn present(&mut self, renderer: &mut Renderer, viewport: &Viewport) {
-            self.maybe_resize_buffers(viewport.physical_size());
+        /// Rasterize what's drawn with the renderer, without showing it anywhere yet.
+        pub fn render(&mut self, renderer: &mut Renderer, viewport: &Viewport) {
+            self.maybe_resize_buffers(viewport);
             self.render_to_surface(renderer, viewport);
-            self.draw_texture();
         }
+
+        pub fn upload_to(&mut self, texture: &Texture2D) {
+            self.surface.upload_to(texture);
+        }
+
+        pub fn to_image(&self) -> Image {
+            self.surface.to_image()
+        }
+
+        /// Blend the surface over `image`, at a `position` in logical pixels.
+        pub fn blend_onto(&self, image: &mut Image, position: Point) {
+            let position = position * iced_core::Transformation::scale(self.scale_factor as f32);
+            let (x, y) = (position.x.round() as i32, position.y.round() as i32);
+            self.surface.blend_onto(image, x, y);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        use macroquad::camera::Camera2D;
+        use macroquad::math::{vec2, vec3};
+
+        #[test]
+        fn surface_blends_over_the_game() {
+            let mut surface = Surface::new(Size::new(2, 1));
+            // Half transparent red, premultiplied, next to a fully transparent pixel.
+            surface
+                .data_mut(&[], 1.0)
+                .copy_from_slice(&[128, 0, 0, 128, 0, 0, 0, 0]);
+
+            let mut image = Image {
+                bytes: [0, 0, 200, 255].repeat(3),
+                width: 3,
+                height: 1,
+            };
+            surface.blend_onto(&mut image, 1, 0);
+
+            assert_eq!(
+                image.bytes,
+                [0, 0, 200, 255, 128, 0, 100, 255, 0, 0, 200, 255]
+            );
+        }
+
+        /// Where a logical point lands on the window through `camera`, in physical pixels.
+        fn to_window(camera: &dyn Camera, point: Point, window: Size<f32>) -> Point {
+            let ndc = camera.matrix().project_point3(vec3(point.x, point.y, 0.0));
+            Point::new(
+                (ndc.x + 1.0) / 2.0 * window.width,
+                (1.0 - ndc.y) / 2.0 * window.height,
+            )
+        }
+
+        /// Covers the camera the UI is drawn with, which puts the quad on the physical pixels of
+        /// its logical position where a zoomed game camera doesn't. That
+        /// `Compositor::draw_texture` swaps it in and restores the game's camera needs a window,
+        /// so that isn't tested here.
+        #[test]
+        fn ui_quad_lands_on_its_screen_pixels() {
+            let (window, dpi) = (Size::new(1600.0, 1200.0), 2.0);
+            let screen = ScreenCamera::new(Size::new(window.width / dpi, window.height / dpi));
+            let zoomed = Camera2D {
+                zoom: vec2(0.01, 0.01),
+                target: vec2(300.0, -40.0),
+                ..Default::default()
+            };
+
+            // The corners of a UI drawn at (40, 30), with a logical size of 200 by 100.
+            for corner in [Point::new(40.0, 30.0), Point::new(240.0, 130.0)] {
+                let expected = Point::new(corner.x * dpi, corner.y * dpi);
+
+                assert!(to_window(&screen, corner, window).distance(expected) < 0.01);
+                // The game's camera would have put it elsewhere.
+                assert!(to_window(&zoomed, corner, window).distance(expected) > 1.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use iced_core::{keyboard, mouse, Point};
+
+    fn events() -> Vec<Event> {
+        vec![
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)),
+            Event::Mouse(mouse::Event::CursorMoved {
+                position: Point::new(10.0, 20.0),
+            }),
+            Event::Keyboard(keyboard::Event::ModifiersChanged(
+                keyboard::Modifiers::SHIFT,
+            )),
+        ]
+    }
+
+    fn deliver(frame_events: &FrameEvents, frame: u64, received: &mut Option<u64>) -> Vec<usize> {
+        let mut delivered = Vec::new();
+        frame_events.deliver(frame, received, &mut delivered);
+        delivered.into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn captured_events_are_hidden_from_lower_interfaces() {
+        let mut frame_events = FrameEvents::default();
+        let (mut top, mut middle, mut bottom) = (None, None, None);
+
+        frame_events.start(1, events());
+        assert_eq!(deliver(&frame_events, 1, &mut top), [0, 1, 2]);
+        frame_events.capture(0);
+        assert_eq!(deliver(&frame_events, 1, &mut middle), [1, 2]);
+        frame_events.capture(2);
+        assert_eq!(deliver(&frame_events, 1, &mut bottom), [1]);
+
+        // Captures only last a frame.
+        frame_events.start(2, events());
+        assert_eq!(deliver(&frame_events, 2, &mut bottom), [0, 1, 2]);
+    }
+
+    #[test]
+    fn second_update_in_a_frame_gets_no_input() {
+        let mut frame_events = FrameEvents::default();
+        let mut received = None;
+
+        frame_events.start(1, events());
+        assert_eq!(deliver(&frame_events, 1, &mut received), [0, 1, 2]);
+        assert_eq!(deliver(&frame_events, 1, &mut received), []);
+
+        frame_events.start(2, events());
+        assert_eq!(deliver(&frame_events, 2, &mut received), [0, 1, 2]);
     }
 }
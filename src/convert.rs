@@ -1,6 +1,7 @@
 use crate::mq;
 
-use iced_core::keyboard::{key::Named, Key, Location, Modifiers};
+use iced_core::keyboard::key::{Code, Named, NativeCode, Physical};
+use iced_core::keyboard::{Key, Location, Modifiers};
 use iced_core::mouse::{Button, Interaction};
 use iced_core::{touch, Point};
 
@@ -36,6 +37,17 @@ pub fn key_mod(md: mq::KeyMods) -> Modifiers {
         | flag(Modifiers::LOGO, md.logo)
 }
 
+/// The modifier a key toggles, if it is a modifier key.
+pub fn modifier(key: mq::KeyCode) -> Modifiers {
+    match key {
+        mq::KeyCode::LeftShift | mq::KeyCode::RightShift => Modifiers::SHIFT,
+        mq::KeyCode::LeftControl | mq::KeyCode::RightControl => Modifiers::CTRL,
+        mq::KeyCode::LeftAlt | mq::KeyCode::RightAlt => Modifiers::ALT,
+        mq::KeyCode::LeftSuper | mq::KeyCode::RightSuper => Modifiers::LOGO,
+        _ => Modifiers::empty(),
+    }
+}
+
 pub fn key(key: mq::KeyCode) -> (Key, Location) {
     let mut location = Location::Standard;
     let l = &mut location;
@@ -79,7 +91,7 @@ pub fn key(key: mq::KeyCode) -> (Key, Location) {
         mq::KeyCode::Key8 => char("8"),
         mq::KeyCode::Key9 => char("9"),
         mq::KeyCode::Semicolon => char(";"),
-        mq::KeyCode::Equal => char(","),
+        mq::KeyCode::Equal => char("="),
         mq::KeyCode::A => char("a"),
         mq::KeyCode::B => char("b"),
         mq::KeyCode::C => char("c"),
@@ -107,9 +119,13 @@ pub fn key(key: mq::KeyCode) -> (Key, Location) {
         mq::KeyCode::Y => char("y"),
         mq::KeyCode::Z => char("z"),
 
-        mq::KeyCode::LeftBracket => char("("),
+        mq::KeyCode::LeftBracket => char("["),
         mq::KeyCode::Backslash => char("\\"),
-        mq::KeyCode::RightBracket => char(")"),
+        mq::KeyCode::RightBracket => char("]"),
+        mq::KeyCode::GraveAccent => char("`"),
+        // Layout specific keys, there's no way to know what they produce.
+        mq::KeyCode::World1 => Key::Unidentified,
+        mq::KeyCode::World2 => Key::Unidentified,
 
         mq::KeyCode::Escape => named(Named::Escape),
         mq::KeyCode::Enter => named(Named::Enter),
@@ -129,7 +145,7 @@ pub fn key(key: mq::KeyCode) -> (Key, Location) {
         mq::KeyCode::ScrollLock => named(Named::ScrollLock),
         mq::KeyCode::NumLock => named(Named::NumLock),
         mq::KeyCode::PrintScreen => named(Named::PrintScreen),
-        mq::KeyCode::Pause => named(Named::MediaPause),
+        mq::KeyCode::Pause => named(Named::Pause),
         mq::KeyCode::F1 => named(Named::F1),
         mq::KeyCode::F2 => named(Named::F2),
         mq::KeyCode::F3 => named(Named::F3),
@@ -182,16 +198,190 @@ pub fn key(key: mq::KeyCode) -> (Key, Location) {
         mq::KeyCode::RightControl => named_right(Named::Control, l),
         mq::KeyCode::RightAlt => named_right(Named::Alt, l),
         mq::KeyCode::RightSuper => named_right(Named::Super, l),
+        mq::KeyCode::Menu => named(Named::ContextMenu),
+        mq::KeyCode::Back => named(Named::GoBack),
         mq::KeyCode::Unknown => Key::Unidentified,
-        _ => Key::Unidentified,
     };
 
     (key, location)
 }
 
-pub fn touch(phase: mq::TouchPhase, id: u64, x: f32, y: f32) -> touch::Event {
+pub fn physical_key(key: mq::KeyCode) -> Physical {
+    let code = match key {
+        mq::KeyCode::Space => Code::Space,
+        mq::KeyCode::Apostrophe => Code::Quote,
+        mq::KeyCode::Comma => Code::Comma,
+        mq::KeyCode::Minus => Code::Minus,
+        mq::KeyCode::Period => Code::Period,
+        mq::KeyCode::Slash => Code::Slash,
+
+        mq::KeyCode::Key0 => Code::Digit0,
+        mq::KeyCode::Key1 => Code::Digit1,
+        mq::KeyCode::Key2 => Code::Digit2,
+        mq::KeyCode::Key3 => Code::Digit3,
+        mq::KeyCode::Key4 => Code::Digit4,
+        mq::KeyCode::Key5 => Code::Digit5,
+        mq::KeyCode::Key6 => Code::Digit6,
+        mq::KeyCode::Key7 => Code::Digit7,
+        mq::KeyCode::Key8 => Code::Digit8,
+        mq::KeyCode::Key9 => Code::Digit9,
+        mq::KeyCode::Semicolon => Code::Semicolon,
+        mq::KeyCode::Equal => Code::Equal,
+        mq::KeyCode::A => Code::KeyA,
+        mq::KeyCode::B => Code::KeyB,
+        mq::KeyCode::C => Code::KeyC,
+        mq::KeyCode::D => Code::KeyD,
+        mq::KeyCode::E => Code::KeyE,
+        mq::KeyCode::F => Code::KeyF,
+        mq::KeyCode::G => Code::KeyG,
+        mq::KeyCode::H => Code::KeyH,
+        mq::KeyCode::I => Code::KeyI,
+        mq::KeyCode::J => Code::KeyJ,
+        mq::KeyCode::K => Code::KeyK,
+        mq::KeyCode::L => Code::KeyL,
+        mq::KeyCode::M => Code::KeyM,
+        mq::KeyCode::N => Code::KeyN,
+        mq::KeyCode::O => Code::KeyO,
+        mq::KeyCode::P => Code::KeyP,
+        mq::KeyCode::Q => Code::KeyQ,
+        mq::KeyCode::R => Code::KeyR,
+        mq::KeyCode::S => Code::KeyS,
+        mq::KeyCode::T => Code::KeyT,
+        mq::KeyCode::U => Code::KeyU,
+        mq::KeyCode::V => Code::KeyV,
+        mq::KeyCode::W => Code::KeyW,
+        mq::KeyCode::X => Code::KeyX,
+        mq::KeyCode::Y => Code::KeyY,
+        mq::KeyCode::Z => Code::KeyZ,
+
+        mq::KeyCode::LeftBracket => Code::BracketLeft,
+        mq::KeyCode::Backslash => Code::Backslash,
+        mq::KeyCode::RightBracket => Code::BracketRight,
+        mq::KeyCode::GraveAccent => Code::Backquote,
+        mq::KeyCode::World1 => Code::IntlBackslash,
+        mq::KeyCode::World2 => Code::IntlRo,
+
+        mq::KeyCode::Escape => Code::Escape,
+        mq::KeyCode::Enter => Code::Enter,
+        mq::KeyCode::Tab => Code::Tab,
+        mq::KeyCode::Backspace => Code::Backspace,
+        mq::KeyCode::Insert => Code::Insert,
+        mq::KeyCode::Delete => Code::Delete,
+        mq::KeyCode::Right => Code::ArrowRight,
+        mq::KeyCode::Left => Code::ArrowLeft,
+        mq::KeyCode::Down => Code::ArrowDown,
+        mq::KeyCode::Up => Code::ArrowUp,
+        mq::KeyCode::PageUp => Code::PageUp,
+        mq::KeyCode::PageDown => Code::PageDown,
+        mq::KeyCode::Home => Code::Home,
+        mq::KeyCode::End => Code::End,
+        mq::KeyCode::CapsLock => Code::CapsLock,
+        mq::KeyCode::ScrollLock => Code::ScrollLock,
+        mq::KeyCode::NumLock => Code::NumLock,
+        mq::KeyCode::PrintScreen => Code::PrintScreen,
+        mq::KeyCode::Pause => Code::Pause,
+        mq::KeyCode::F1 => Code::F1,
+        mq::KeyCode::F2 => Code::F2,
+        mq::KeyCode::F3 => Code::F3,
+        mq::KeyCode::F4 => Code::F4,
+        mq::KeyCode::F5 => Code::F5,
+        mq::KeyCode::F6 => Code::F6,
+        mq::KeyCode::F7 => Code::F7,
+        mq::KeyCode::F8 => Code::F8,
+        mq::KeyCode::F9 => Code::F9,
+        mq::KeyCode::F10 => Code::F10,
+        mq::KeyCode::F11 => Code::F11,
+        mq::KeyCode::F12 => Code::F12,
+        mq::KeyCode::F13 => Code::F13,
+        mq::KeyCode::F14 => Code::F14,
+        mq::KeyCode::F15 => Code::F15,
+        mq::KeyCode::F16 => Code::F16,
+        mq::KeyCode::F17 => Code::F17,
+        mq::KeyCode::F18 => Code::F18,
+        mq::KeyCode::F19 => Code::F19,
+        mq::KeyCode::F20 => Code::F20,
+        mq::KeyCode::F21 => Code::F21,
+        mq::KeyCode::F22 => Code::F22,
+        mq::KeyCode::F23 => Code::F23,
+        mq::KeyCode::F24 => Code::F24,
+        mq::KeyCode::F25 => Code::F25,
+
+        mq::KeyCode::Kp0 => Code::Numpad0,
+        mq::KeyCode::Kp1 => Code::Numpad1,
+        mq::KeyCode::Kp2 => Code::Numpad2,
+        mq::KeyCode::Kp3 => Code::Numpad3,
+        mq::KeyCode::Kp4 => Code::Numpad4,
+        mq::KeyCode::Kp5 => Code::Numpad5,
+        mq::KeyCode::Kp6 => Code::Numpad6,
+        mq::KeyCode::Kp7 => Code::Numpad7,
+        mq::KeyCode::Kp8 => Code::Numpad8,
+        mq::KeyCode::Kp9 => Code::Numpad9,
+
+        mq::KeyCode::KpDecimal => Code::NumpadDecimal,
+        mq::KeyCode::KpDivide => Code::NumpadDivide,
+        mq::KeyCode::KpMultiply => Code::NumpadMultiply,
+        mq::KeyCode::KpSubtract => Code::NumpadSubtract,
+        mq::KeyCode::KpAdd => Code::NumpadAdd,
+        mq::KeyCode::KpEnter => Code::NumpadEnter,
+        mq::KeyCode::KpEqual => Code::NumpadEqual,
+        mq::KeyCode::LeftShift => Code::ShiftLeft,
+        mq::KeyCode::LeftControl => Code::ControlLeft,
+        mq::KeyCode::LeftAlt => Code::AltLeft,
+        mq::KeyCode::LeftSuper => Code::SuperLeft,
+        mq::KeyCode::RightShift => Code::ShiftRight,
+        mq::KeyCode::RightControl => Code::ControlRight,
+        mq::KeyCode::RightAlt => Code::AltRight,
+        mq::KeyCode::RightSuper => Code::SuperRight,
+        mq::KeyCode::Menu => Code::ContextMenu,
+        mq::KeyCode::Back => Code::BrowserBack,
+        mq::KeyCode::Unknown => return Physical::Unidentified(NativeCode::Unidentified),
+    };
+
+    Physical::Code(code)
+}
+
+/// Applies the modifiers to a logical key, as it would appear on a US layout.
+///
+/// This is a best guess, the text from a `char_event` takes priority when available.
+pub fn modified_key(key: &Key, location: Location, modifiers: Modifiers) -> Key {
+    let Key::Character(c) = key else {
+        return key.clone();
+    };
+
+    if !modifiers.shift() || location == Location::Numpad {
+        return key.clone();
+    }
+
+    let shifted = match c.as_str() {
+        "1" => "!",
+        "2" => "@",
+        "3" => "#",
+        "4" => "$",
+        "5" => "%",
+        "6" => "^",
+        "7" => "&",
+        "8" => "*",
+        "9" => "(",
+        "0" => ")",
+        "-" => "_",
+        "=" => "+",
+        "[" => "{",
+        "]" => "}",
+        "\\" => "|",
+        ";" => ":",
+        "'" => "\"",
+        "," => "<",
+        "." => ">",
+        "/" => "?",
+        "`" => "~",
+        c => return Key::Character(c.to_uppercase().into()),
+    };
+
+    char(shifted)
+}
+
+pub fn touch(phase: mq::TouchPhase, id: u64, position: Point) -> touch::Event {
     let id = touch::Finger(id);
-    let position = Point::new(x, y);
 
     match phase {
         mq::TouchPhase::Started => touch::Event::FingerPressed { id, position },
@@ -217,3 +407,157 @@ pub fn cursor_icon(icon: Interaction) -> mq::CursorIcon {
         _ =>  mq::CursorIcon::Default // todo
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    #[rustfmt::skip]
+    fn key_table() -> Vec<(mq::KeyCode, Key, Location)> {
+        vec![
+            (mq::KeyCode::Space, named(Named::Space), Location::Standard),
+            (mq::KeyCode::Apostrophe, char("'"), Location::Standard),
+            (mq::KeyCode::Comma, char(","), Location::Standard),
+            (mq::KeyCode::Minus, char("-"), Location::Standard),
+            (mq::KeyCode::Period, char("."), Location::Standard),
+            (mq::KeyCode::Slash, char("/"), Location::Standard),
+            (mq::KeyCode::Key0, char("0"), Location::Standard),
+            (mq::KeyCode::Key1, char("1"), Location::Standard),
+            (mq::KeyCode::Key2, char("2"), Location::Standard),
+            (mq::KeyCode::Key3, char("3"), Location::Standard),
+            (mq::KeyCode::Key4, char("4"), Location::Standard),
+            (mq::KeyCode::Key5, char("5"), Location::Standard),
+            (mq::KeyCode::Key6, char("6"), Location::Standard),
+            (mq::KeyCode::Key7, char("7"), Location::Standard),
+            (mq::KeyCode::Key8, char("8"), Location::Standard),
+            (mq::KeyCode::Key9, char("9"), Location::Standard),
+            (mq::KeyCode::Semicolon, char(";"), Location::Standard),
+            (mq::KeyCode::Equal, char("="), Location::Standard),
+            (mq::KeyCode::A, char("a"), Location::Standard),
+            (mq::KeyCode::B, char("b"), Location::Standard),
+            (mq::KeyCode::C, char("c"), Location::Standard),
+            (mq::KeyCode::D, char("d"), Location::Standard),
+            (mq::KeyCode::E, char("e"), Location::Standard),
+            (mq::KeyCode::F, char("f"), Location::Standard),
+            (mq::KeyCode::G, char("g"), Location::Standard),
+            (mq::KeyCode::H, char("h"), Location::Standard),
+            (mq::KeyCode::I, char("i"), Location::Standard),
+            (mq::KeyCode::J, char("j"), Location::Standard),
+            (mq::KeyCode::K, char("k"), Location::Standard),
+            (mq::KeyCode::L, char("l"), Location::Standard),
+            (mq::KeyCode::M, char("m"), Location::Standard),
+            (mq::KeyCode::N, char("n"), Location::Standard),
+            (mq::KeyCode::O, char("o"), Location::Standard),
+            (mq::KeyCode::P, char("p"), Location::Standard),
+            (mq::KeyCode::Q, char("q"), Location::Standard),
+            (mq::KeyCode::R, char("r"), Location::Standard),
+            (mq::KeyCode::S, char("s"), Location::Standard),
+            (mq::KeyCode::T, char("t"), Location::Standard),
+            (mq::KeyCode::U, char("u"), Location::Standard),
+            (mq::KeyCode::V, char("v"), Location::Standard),
+            (mq::KeyCode::W, char("w"), Location::Standard),
+            (mq::KeyCode::X, char("x"), Location::Standard),
+            (mq::KeyCode::Y, char("y"), Location::Standard),
+            (mq::KeyCode::Z, char("z"), Location::Standard),
+            (mq::KeyCode::LeftBracket, char("["), Location::Standard),
+            (mq::KeyCode::Backslash, char("\\"), Location::Standard),
+            (mq::KeyCode::RightBracket, char("]"), Location::Standard),
+            (mq::KeyCode::GraveAccent, char("`"), Location::Standard),
+            (mq::KeyCode::World1, Key::Unidentified, Location::Standard),
+            (mq::KeyCode::World2, Key::Unidentified, Location::Standard),
+            (mq::KeyCode::Escape, named(Named::Escape), Location::Standard),
+            (mq::KeyCode::Enter, named(Named::Enter), Location::Standard),
+            (mq::KeyCode::Tab, named(Named::Tab), Location::Standard),
+            (mq::KeyCode::Backspace, named(Named::Backspace), Location::Standard),
+            (mq::KeyCode::Insert, named(Named::Insert), Location::Standard),
+            (mq::KeyCode::Delete, named(Named::Delete), Location::Standard),
+            (mq::KeyCode::Right, named(Named::ArrowRight), Location::Standard),
+            (mq::KeyCode::Left, named(Named::ArrowLeft), Location::Standard),
+            (mq::KeyCode::Down, named(Named::ArrowDown), Location::Standard),
+            (mq::KeyCode::Up, named(Named::ArrowUp), Location::Standard),
+            (mq::KeyCode::PageUp, named(Named::PageUp), Location::Standard),
+            (mq::KeyCode::PageDown, named(Named::PageDown), Location::Standard),
+            (mq::KeyCode::Home, named(Named::Home), Location::Standard),
+            (mq::KeyCode::End, named(Named::End), Location::Standard),
+            (mq::KeyCode::CapsLock, named(Named::CapsLock), Location::Standard),
+            (mq::KeyCode::ScrollLock, named(Named::ScrollLock), Location::Standard),
+            (mq::KeyCode::NumLock, named(Named::NumLock), Location::Standard),
+            (mq::KeyCode::PrintScreen, named(Named::PrintScreen), Location::Standard),
+            (mq::KeyCode::Pause, named(Named::Pause), Location::Standard),
+            (mq::KeyCode::F1, named(Named::F1), Location::Standard),
+            (mq::KeyCode::F2, named(Named::F2), Location::Standard),
+            (mq::KeyCode::F3, named(Named::F3), Location::Standard),
+            (mq::KeyCode::F4, named(Named::F4), Location::Standard),
+            (mq::KeyCode::F5, named(Named::F5), Location::Standard),
+            (mq::KeyCode::F6, named(Named::F6), Location::Standard),
+            (mq::KeyCode::F7, named(Named::F7), Location::Standard),
+            (mq::KeyCode::F8, named(Named::F8), Location::Standard),
+            (mq::KeyCode::F9, named(Named::F9), Location::Standard),
+            (mq::KeyCode::F10, named(Named::F10), Location::Standard),
+            (mq::KeyCode::F11, named(Named::F11), Location::Standard),
+            (mq::KeyCode::F12, named(Named::F12), Location::Standard),
+            (mq::KeyCode::F13, named(Named::F13), Location::Standard),
+            (mq::KeyCode::F14, named(Named::F14), Location::Standard),
+            (mq::KeyCode::F15, named(Named::F15), Location::Standard),
+            (mq::KeyCode::F16, named(Named::F16), Location::Standard),
+            (mq::KeyCode::F17, named(Named::F17), Location::Standard),
+            (mq::KeyCode::F18, named(Named::F18), Location::Standard),
+            (mq::KeyCode::F19, named(Named::F19), Location::Standard),
+            (mq::KeyCode::F20, named(Named::F20), Location::Standard),
+            (mq::KeyCode::F21, named(Named::F21), Location::Standard),
+            (mq::KeyCode::F22, named(Named::F22), Location::Standard),
+            (mq::KeyCode::F23, named(Named::F23), Location::Standard),
+            (mq::KeyCode::F24, named(Named::F24), Location::Standard),
+            (mq::KeyCode::F25, named(Named::F25), Location::Standard),
+            (mq::KeyCode::Kp0, char("0"), Location::Numpad),
+            (mq::KeyCode::Kp1, char("1"), Location::Numpad),
+            (mq::KeyCode::Kp2, char("2"), Location::Numpad),
+            (mq::KeyCode::Kp3, char("3"), Location::Numpad),
+            (mq::KeyCode::Kp4, char("4"), Location::Numpad),
+            (mq::KeyCode::Kp5, char("5"), Location::Numpad),
+            (mq::KeyCode::Kp6, char("6"), Location::Numpad),
+            (mq::KeyCode::Kp7, char("7"), Location::Numpad),
+            (mq::KeyCode::Kp8, char("8"), Location::Numpad),
+            (mq::KeyCode::Kp9, char("9"), Location::Numpad),
+            (mq::KeyCode::KpDecimal, char("."), Location::Numpad),
+            (mq::KeyCode::KpDivide, char("/"), Location::Numpad),
+            (mq::KeyCode::KpMultiply, char("*"), Location::Numpad),
+            (mq::KeyCode::KpSubtract, char("-"), Location::Numpad),
+            (mq::KeyCode::KpAdd, char("+"), Location::Numpad),
+            (mq::KeyCode::KpEnter, named(Named::Enter), Location::Numpad),
+            (mq::KeyCode::KpEqual, char("="), Location::Numpad),
+            (mq::KeyCode::LeftShift, named(Named::Shift), Location::Left),
+            (mq::KeyCode::LeftControl, named(Named::Control), Location::Left),
+            (mq::KeyCode::LeftAlt, named(Named::Alt), Location::Left),
+            (mq::KeyCode::LeftSuper, named(Named::Super), Location::Left),
+            (mq::KeyCode::RightShift, named(Named::Shift), Location::Right),
+            (mq::KeyCode::RightControl, named(Named::Control), Location::Right),
+            (mq::KeyCode::RightAlt, named(Named::Alt), Location::Right),
+            (mq::KeyCode::RightSuper, named(Named::Super), Location::Right),
+            (mq::KeyCode::Menu, named(Named::ContextMenu), Location::Standard),
+            (mq::KeyCode::Back, named(Named::GoBack), Location::Standard),
+            (mq::KeyCode::Unknown, Key::Unidentified, Location::Standard),
+        ]
+    }
+
+    #[test]
+    fn keys_translate_to_expected_key_and_location() {
+        for (code, expected_key, expected_location) in key_table() {
+            let (key, location) = key(code);
+
+            assert_eq!(key, expected_key, "key for {code:?}");
+            assert_eq!(location, expected_location, "location for {code:?}");
+        }
+    }
+
+    #[test]
+    fn key_table_has_no_duplicates() {
+        let mut seen = HashSet::new();
+
+        for (code, _, _) in key_table() {
+            assert!(seen.insert(code), "{code:?} is listed twice");
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::convert;
 use crate::mq;
 
-use iced_core::{keyboard, mouse, window, Event, Point, Size};
+use iced_core::keyboard::{self, key, Key, Location, Modifiers};
+use iced_core::{mouse, window, Event, Point, Size, SmolStr};
 
 pub trait EventProxy {
     fn add(&mut self, event: Event);
@@ -22,28 +23,79 @@ where
     }
 }
 
-pub struct EventProxyWrapper<T: EventProxy>(pub T);
+/// Keyboard state that has to outlive a single pass over miniquad's input queue.
+#[derive(Debug, Default)]
+pub struct KeyboardState {
+    modifiers: Modifiers,
+}
+
+pub struct EventProxyWrapper<'a, T: EventProxy> {
+    proxy: T,
+    keyboard: &'a mut KeyboardState,
+    /// miniquad reports physical pixels, iced works in logical pixels.
+    scale_factor: f32,
+    /// A key press waiting to see if miniquad follows it up with a `char_event`.
+    pending_key: Option<keyboard::Event>,
+}
+
+impl<'a, T: EventProxy> EventProxyWrapper<'a, T> {
+    pub fn new(proxy: T, keyboard: &'a mut KeyboardState, scale_factor: f32) -> Self {
+        Self {
+            proxy,
+            keyboard,
+            scale_factor,
+            pending_key: None,
+        }
+    }
+
+    /// Send off any key press that is still waiting for its text.
+    pub fn finish(mut self) {
+        self.flush_key();
+    }
 
-impl<T: EventProxy> EventProxy for EventProxyWrapper<T> {
+    fn logical(&self, x: f32, y: f32) -> Point {
+        Point::new(x / self.scale_factor, y / self.scale_factor)
+    }
+
+    fn flush_key(&mut self) {
+        if let Some(event) = self.pending_key.take() {
+            self.proxy.add(Event::Keyboard(event))
+        }
+    }
+
+    fn update_modifiers(&mut self, modifiers: Modifiers) {
+        if self.keyboard.modifiers != modifiers {
+            self.keyboard.modifiers = modifiers;
+            self.add(Event::Keyboard(keyboard::Event::ModifiersChanged(
+                modifiers,
+            )))
+        }
+    }
+}
+
+impl<T: EventProxy> EventProxy for EventProxyWrapper<'_, T> {
     fn add(&mut self, event: Event) {
-        self.0.add(event)
+        // Preserve ordering, a pending key press happened before this event.
+        self.flush_key();
+        self.proxy.add(event)
     }
 }
 
-impl<T: EventProxy> mq::EventHandler for EventProxyWrapper<T> {
+impl<T: EventProxy> mq::EventHandler for EventProxyWrapper<'_, T> {
     fn update(&mut self) {}
 
     fn draw(&mut self) {}
 
     fn resize_event(&mut self, width: f32, height: f32) {
+        let size = self.logical(width, height);
         self.add(Event::Window(window::Event::Resized(Size::new(
-            width, height,
+            size.x, size.y,
         ))))
     }
 
     fn mouse_motion_event(&mut self, x: f32, y: f32) {
         self.add(Event::Mouse(mouse::Event::CursorMoved {
-            position: Point::new(x, y),
+            position: self.logical(x, y),
         }))
     }
 
@@ -65,31 +117,74 @@ impl<T: EventProxy> mq::EventHandler for EventProxyWrapper<T> {
         }
     }
 
-    fn char_event(&mut self, _character: char, _keymods: mq::KeyMods, _repeat: bool) {}
+    fn char_event(&mut self, character: char, keymods: mq::KeyMods, _repeat: bool) {
+        // Backspace, enter, tab, etc. are already covered by their key press.
+        if character.is_control() {
+            return;
+        }
+
+        // Chords are shortcuts, not text. AltGr is reported as Ctrl + Alt on some platforms.
+        if (keymods.ctrl && !keymods.alt) || keymods.logo {
+            return;
+        }
+
+        // The character already has shift, caps lock and the keyboard layout applied.
+        let character = SmolStr::new(character.encode_utf8(&mut [0; 4]));
+
+        if let Some(keyboard::Event::KeyPressed {
+            modified_key,
+            text: text @ None,
+            ..
+        }) = &mut self.pending_key
+        {
+            *modified_key = Key::Character(character.clone());
+            *text = Some(character);
+            self.flush_key();
+            return;
+        }
+
+        // Nothing to merge with, e.g. a character miniquad has no key code for.
+        self.add(Event::Keyboard(keyboard::Event::KeyPressed {
+            key: Key::Character(character.clone()),
+            modified_key: Key::Character(character.clone()),
+            physical_key: key::Physical::Unidentified(key::NativeCode::Unidentified),
+            location: Location::Standard,
+            modifiers: convert::key_mod(keymods),
+            text: Some(character),
+        }))
+    }
 
     fn key_down_event(&mut self, keycode: mq::KeyCode, keymods: mq::KeyMods, _repeat: bool) {
+        let modifiers = convert::key_mod(keymods) | convert::modifier(keycode);
+        self.update_modifiers(modifiers);
+
         let (key, location) = convert::key(keycode);
-        self.add(Event::Keyboard(keyboard::Event::KeyPressed {
+        self.flush_key();
+        self.pending_key = Some(keyboard::Event::KeyPressed {
+            modified_key: convert::modified_key(&key, location, modifiers),
+            physical_key: convert::physical_key(keycode),
             key,
-            modified_key: keyboard::Key::Unidentified, //todo!
-            physical_key: keyboard::key::Physical::Code(keyboard::key::Code::Space), // todo
             location,
-            modifiers: convert::key_mod(keymods),
+            modifiers,
             text: None,
-        }))
+        });
     }
 
     fn key_up_event(&mut self, keycode: mq::KeyCode, keymods: mq::KeyMods) {
+        let modifiers = convert::key_mod(keymods).difference(convert::modifier(keycode));
+        self.update_modifiers(modifiers);
+
         let (key, location) = convert::key(keycode);
         self.add(Event::Keyboard(keyboard::Event::KeyReleased {
             key,
             location,
-            modifiers: convert::key_mod(keymods),
+            modifiers,
         }))
     }
 
     fn touch_event(&mut self, phase: mq::TouchPhase, id: u64, x: f32, y: f32) {
-        self.add(Event::Touch(convert::touch(phase, id, x, y)));
+        let position = self.logical(x, y);
+        self.add(Event::Touch(convert::touch(phase, id, position)));
     }
 
     fn raw_mouse_motion(&mut self, _dx: f32, _dy: f32) {}
@@ -106,3 +201,125 @@ impl<T: EventProxy> mq::EventHandler for EventProxyWrapper<T> {
 
     fn files_dropped_event(&mut self) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mq::{EventHandler, KeyCode, KeyMods};
+
+    const NONE: KeyMods = KeyMods {
+        shift: false,
+        ctrl: false,
+        alt: false,
+        logo: false,
+    };
+
+    fn events(input: impl FnOnce(&mut EventProxyWrapper<&mut Vec<Event>>)) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut keyboard = KeyboardState::default();
+        let mut wrapper = EventProxyWrapper::new(&mut events, &mut keyboard, 1.0);
+        input(&mut wrapper);
+        wrapper.finish();
+        events
+    }
+
+    /// The key, modified key and text of every key press.
+    fn presses(events: &[Event]) -> Vec<(Key, Key, Option<SmolStr>)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key,
+                    modified_key,
+                    text,
+                    ..
+                }) => Some((key.clone(), modified_key.clone(), text.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn char(c: &str) -> Key {
+        Key::Character(c.into())
+    }
+
+    #[test]
+    fn shifted_characters_are_merged_with_their_key() {
+        let shift = KeyMods {
+            shift: true,
+            ..NONE
+        };
+        let events = events(|input| {
+            input.key_down_event(KeyCode::Key2, shift, false);
+            input.char_event('@', shift, false);
+        });
+
+        assert_eq!(presses(&events), [(char("2"), char("@"), Some("@".into()))]);
+        assert!(matches!(
+            events[0],
+            Event::Keyboard(keyboard::Event::ModifiersChanged(Modifiers::SHIFT))
+        ));
+    }
+
+    #[test]
+    fn chords_produce_no_text() {
+        for keymods in [
+            KeyMods { ctrl: true, ..NONE },
+            KeyMods { logo: true, ..NONE },
+        ] {
+            let events = events(|input| {
+                input.key_down_event(KeyCode::C, keymods, false);
+                input.char_event('c', keymods, false);
+            });
+
+            assert_eq!(presses(&events), [(char("c"), char("c"), None)]);
+        }
+    }
+
+    #[test]
+    fn altgr_produces_text() {
+        let altgr = KeyMods {
+            ctrl: true,
+            alt: true,
+            ..NONE
+        };
+        let events = events(|input| {
+            input.key_down_event(KeyCode::Q, altgr, false);
+            input.char_event('@', altgr, false);
+        });
+
+        assert_eq!(presses(&events), [(char("q"), char("@"), Some("@".into()))]);
+    }
+
+    #[test]
+    fn characters_without_a_key_code_are_pressed() {
+        let events = events(|input| input.char_event('é', NONE, false));
+
+        assert_eq!(presses(&events), [(char("é"), char("é"), Some("é".into()))]);
+        assert!(matches!(
+            events[0],
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                physical_key: key::Physical::Unidentified(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn finish_sends_the_pending_press() {
+        let mut events = Vec::new();
+        let mut keyboard = KeyboardState::default();
+
+        let mut input = EventProxyWrapper::new(&mut events, &mut keyboard, 1.0);
+        input.key_down_event(KeyCode::Escape, NONE, false);
+        drop(input);
+        assert!(events.is_empty(), "still waiting for a char_event");
+
+        let mut input = EventProxyWrapper::new(&mut events, &mut keyboard, 1.0);
+        input.key_down_event(KeyCode::Escape, NONE, false);
+        input.finish();
+        let escape = Key::Named(key::Named::Escape);
+        assert_eq!(presses(&events), [(escape.clone(), escape, None)]);
+    }
+}
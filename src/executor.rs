@@ -0,0 +1,164 @@
+//! Run [`Task`]s a step at a time, from the frame loop.
+//!
+//! macroquad drives the game as a coroutine resumed every frame, so tasks are polled the same
+//! way rather than on other threads, which also keeps them working on the web.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use iced_core::clipboard::Clipboard;
+use iced_core::widget::operation::{Operation, Outcome};
+use iced_runtime::futures::futures::task::noop_waker_ref;
+use iced_runtime::futures::futures::StreamExt;
+use iced_runtime::futures::BoxStream;
+use iced_runtime::{clipboard, task, Action, Task, UserInterface};
+
+use crate::iced::Renderer;
+
+pub(crate) struct Executor<Message> {
+    tasks: Vec<BoxStream<Action<Message>>>,
+    /// Widget operations waiting for the UI to be built.
+    operations: Vec<Box<dyn Operation>>,
+}
+
+impl<Message> Executor<Message> {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    pub fn spawn(&mut self, task: Task<Message>) {
+        self.tasks.extend(task::into_stream(task));
+    }
+
+    /// Poll every task until it has to wait, pushing what they produce to `messages`.
+    ///
+    /// Nothing wakes the tasks up, they're simply polled again next frame. Returns whether any
+    /// messages or widget operations were produced.
+    pub fn poll(&mut self, clipboard: &mut dyn Clipboard, messages: &mut Vec<Message>) -> bool {
+        let mut context = Context::from_waker(noop_waker_ref());
+        let (produced, operations) = (messages.len(), self.operations.len());
+
+        self.tasks.retain_mut(|task| loop {
+            let action = match task.poll_next_unpin(&mut context) {
+                Poll::Ready(Some(action)) => action,
+                Poll::Ready(None) => break false,
+                Poll::Pending => break true,
+            };
+
+            match action {
+                Action::Output(message) => messages.push(message),
+                Action::Widget(operation) => self.operations.push(operation),
+                Action::Clipboard(clipboard::Action::Read { target, channel }) => {
+                    let _ = channel.send(clipboard.read(target));
+                }
+                Action::Clipboard(clipboard::Action::Write { target, contents }) => {
+                    clipboard.write(target, contents)
+                }
+                // An invalid font drops the channel, as there's no error to send back.
+                Action::LoadFont { bytes, channel } => {
+                    if crate::iced::font::load_bytes(bytes).is_ok() {
+                        let _ = channel.send(Ok(()));
+                    }
+                }
+                Action::Exit => crate::mq::window::order_quit(),
+                // macroquad manages the window, and has no system information to give.
+                Action::Window(_) | Action::System(_) => (),
+            }
+        });
+
+        messages.len() > produced || self.operations.len() > operations
+    }
+
+    /// Run the pending widget operations on the UI, such as focusing a text input.
+    pub fn operate<Theme>(
+        &mut self,
+        interface: &mut UserInterface<'_, Message, Theme, Renderer>,
+        renderer: &Renderer,
+    ) {
+        for operation in self.operations.drain(..) {
+            let mut operation = Some(operation);
+
+            while let Some(mut current) = operation.take() {
+                interface.operate(renderer, current.as_mut());
+
+                if let Outcome::Chain(next) = current.finish() {
+                    operation = Some(next);
+                }
+            }
+        }
+    }
+}
+
+/// Poll `future` again every frame until it's ready, even if it never wakes its task.
+///
+/// Futures from macroquad, like the one of `load_file`, never wake their task. That's fine when
+/// they're the whole task, but once combined with [`Task::batch`] they would be left waiting.
+pub fn poll_each_frame<F: Future>(future: F) -> EachFrame<F> {
+    EachFrame(Box::pin(future))
+}
+
+/// A future polled every frame, see [`poll_each_frame`].
+pub struct EachFrame<F>(Pin<Box<F>>);
+
+impl<F: Future> Future for EachFrame<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<F::Output> {
+        let poll = self.0.as_mut().poll(context);
+        if poll.is_pending() {
+            context.waker().wake_by_ref();
+        }
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::{Kind, Memory};
+
+    /// Ready on the second poll, like a file still loading on the first frame.
+    #[derive(Default)]
+    struct NextFrame(bool);
+
+    impl Future for NextFrame {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+            if std::mem::replace(&mut self.0, true) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn tasks_make_progress_each_frame() {
+        let mut executor = Executor::new();
+        let mut clipboard = Memory::new();
+        let mut messages = Vec::new();
+
+        executor.spawn(Task::batch([
+            Task::done("ready".to_owned()),
+            Task::perform(poll_each_frame(NextFrame::default()), |()| {
+                "loaded".to_owned()
+            }),
+            clipboard::write("copied".to_owned()).chain(clipboard::read().map(Option::unwrap)),
+        ]));
+
+        assert!(executor.poll(&mut clipboard, &mut messages));
+        assert!(messages.contains(&"ready".to_owned()));
+        assert!(messages.contains(&"copied".to_owned()));
+        assert_eq!(clipboard.read(Kind::Standard).as_deref(), Some("copied"));
+
+        executor.poll(&mut clipboard, &mut messages);
+        messages.sort();
+        assert_eq!(messages, ["copied", "loaded", "ready"]);
+        assert!(executor.tasks.is_empty());
+    }
+}
@@ -9,10 +9,13 @@ pub use iced_core::color;
 pub use iced_core::gradient;
 pub use iced_core::padding;
 pub use iced_core::theme;
+pub use iced_core::time;
+pub use iced_core::window;
 pub use iced_core::{
     Alignment, Background, Border, Color, ContentFit, Degrees, Gradient, Length, Padding, Pixels,
     Point, Radians, Rectangle, Rotation, Shadow, Size, Theme, Transformation, Vector,
 };
+pub use iced_runtime::{exit, Task};
 
 pub use alignment::Horizontal::{Left, Right};
 pub use alignment::Vertical::{Bottom, Top};
@@ -23,22 +26,214 @@ pub mod font {
     //! Load and use fonts.
     pub use iced_core::font::*;
 
-    pub fn load(fonts: Vec<std::borrow::Cow<'static, [u8]>>) {
+    use std::borrow::Cow;
+    use std::collections::HashSet;
+    use std::fmt;
+    use std::sync::Mutex;
+
+    use iced_graphics::text::cosmic_text::{self, fontdb};
+    use iced_graphics::text::FontSystem;
+
+    /// Families to try, in order, for glyphs no font preferred by cosmic-text has.
+    static LAST_FALLBACKS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// Held by tests that load or reorder fonts, or render text, as reordering changes face ids.
+    #[cfg(test)]
+    pub(crate) static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    /// The header of a TrueType collection with no fonts in it.
+    const EMPTY_COLLECTION: &[u8] = b"ttcf\x00\x01\x00\x00\x00\x00\x00\x00";
+
+    pub fn load(fonts: Vec<Cow<'static, [u8]>>) {
         let mut font_system = iced_graphics::text::font_system()
             .write()
             .expect("Acquire global font system");
 
+        load_faces(&mut font_system, fonts);
+    }
+
+    /// Load a font from a file, or an asset path on the web.
+    ///
+    /// Returns the family name of the font, use it with [`Font::with_name`].
+    pub async fn load_file(path: &str) -> Result<&'static str, Error> {
+        let bytes = macroquad::file::load_file(path)
+            .await
+            .map_err(Error::File)?;
+
+        load_bytes(bytes)
+    }
+
+    /// Load a single font, returning its family name for use with [`Font::with_name`].
+    pub fn load_bytes(bytes: impl Into<Cow<'static, [u8]>>) -> Result<&'static str, Error> {
+        let mut font_system = iced_graphics::text::font_system()
+            .write()
+            .expect("Acquire global font system");
+
+        load_faces(&mut font_system, [bytes.into()]).ok_or(Error::Invalid)
+    }
+
+    /// Set the families to try, in order, for glyphs missing from the font of the text.
+    ///
+    /// Only text shaped with [`Shaping::Advanced`](iced_core::text::Shaping::Advanced) falls
+    /// back to other fonts, widgets like `text` need `.shaping(Shaping::Advanced)` for it. Their
+    /// default, basic shaping, only ever uses the font of the text.
+    ///
+    /// These families can't take precedence over the platform's fonts. cosmic-text, which shapes
+    /// the text, first tries the font of the text, then the fonts it prefers for the script and
+    /// common ones like Noto Sans, DejaVu Sans or emoji fonts, and offers no way to change that.
+    /// Only then are the other loaded fonts tried, these families first. So they fill in for
+    /// glyphs the platform has no font for.
+    ///
+    /// The order is the same for every script, as it's a single list for cosmic-text. A family
+    /// that lacks a glyph is skipped, so list the fonts for every script in one chain, e.g. a
+    /// CJK font followed by an emoji font.
+    ///
+    /// Families are given by name, like the ones returned by [`load_file`]. Set them before
+    /// rendering: text is shaped again afterwards, but renderers created outside of an
+    /// [`Interface`](crate::Interface) may keep showing glyphs of the previous order.
+    pub fn set_last_fallbacks<S: Into<String>>(families: impl IntoIterator<Item = S>) {
+        *LAST_FALLBACKS.lock().unwrap_or_else(|e| e.into_inner()) =
+            families.into_iter().map(Into::into).collect();
+
+        let mut font_system = iced_graphics::text::font_system()
+            .write()
+            .expect("Acquire global font system");
+
+        order_faces(&mut font_system);
+    }
+
+    /// Load fonts, returning the family of the first face found in them.
+    fn load_faces(
+        font_system: &mut FontSystem,
+        fonts: impl IntoIterator<Item = Cow<'static, [u8]>>,
+    ) -> Option<&'static str> {
+        let known: HashSet<_> = font_system.raw().db().faces().map(|face| face.id).collect();
         for font in fonts {
-            font_system.load_font(font)
+            font_system.load_font(font);
+        }
+
+        let db = font_system.raw().db();
+        let new: Vec<_> = db
+            .faces()
+            .filter(|face| !known.contains(&face.id))
+            .collect();
+        let family = new
+            .iter()
+            .find_map(|face| face.families.first())
+            .map(|(family, _)| intern(family));
+
+        // New faces come after every other one, which is only wrong for the fallbacks.
+        let fallbacks = LAST_FALLBACKS.lock().unwrap_or_else(|e| e.into_inner());
+        let reorder = new
+            .iter()
+            .any(|face| rank(&fallbacks, face) < fallbacks.len());
+        drop(fallbacks);
+
+        if reorder {
+            order_faces(font_system);
         }
+
+        family
     }
 
+    /// Where a face is among the fallbacks, past the end if it isn't one.
+    fn rank(fallbacks: &[String], face: &fontdb::FaceInfo) -> usize {
+        fallbacks
+            .iter()
+            .position(|fallback| face.families.iter().any(|(name, _)| name == fallback))
+            .unwrap_or(fallbacks.len())
+    }
+
+    /// Put the faces of the fallbacks first, as the remaining fonts are tried in order of id.
+    fn order_faces(font_system: &mut FontSystem) {
+        let fallbacks = LAST_FALLBACKS.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Ids are handed out in order by a new database, a fresh font system drops any caches.
+        let raw = font_system.raw();
+        let old = std::mem::replace(
+            raw,
+            cosmic_text::FontSystem::new_with_locale_and_db(String::new(), fontdb::Database::new()),
+        );
+        let (locale, old) = old.into_locale_and_db();
+
+        let mut faces: Vec<_> = old.faces().cloned().collect();
+        faces.sort_by_key(|face| rank(&fallbacks, face));
+
+        let mut db = fontdb::Database::new();
+        db.set_serif_family(old.family_name(&fontdb::Family::Serif));
+        db.set_sans_serif_family(old.family_name(&fontdb::Family::SansSerif));
+        db.set_cursive_family(old.family_name(&fontdb::Family::Cursive));
+        db.set_fantasy_family(old.family_name(&fontdb::Family::Fantasy));
+        db.set_monospace_family(old.family_name(&fontdb::Family::Monospace));
+        for face in faces {
+            db.push_face_info(face);
+        }
+
+        *raw = cosmic_text::FontSystem::new_with_locale_and_db(locale, db);
+
+        // Paragraphs are shaped again when the version changes, which only happens when a font
+        // is loaded. A collection holding no fonts bumps it without adding anything.
+        font_system.load_font(Cow::Borrowed(EMPTY_COLLECTION));
+
+        // Renderers cache glyphs by face id, which just changed.
+        crate::context::invalidate_renderers();
+    }
+
+    /// Fonts are named by `&'static str`, so keep one copy of each loaded family name around.
+    fn intern(name: &str) -> &'static str {
+        static NAMES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+        let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+        match names.iter().find(|known| **known == name) {
+            Some(known) => known,
+            None => {
+                let name = Box::leak(name.to_owned().into_boxed_str());
+                names.push(name);
+                name
+            }
+        }
+    }
+
+    /// Why a font couldn't be loaded.
+    #[derive(Debug)]
+    pub enum Error {
+        /// The file couldn't be read.
+        File(macroquad::Error),
+        /// The data doesn't hold a font.
+        Invalid,
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Error::File(error) => write!(f, "failed to read font: {error}"),
+                Error::Invalid => write!(f, "not a valid font"),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    /// Set the font and text size used by text that doesn't specify its own.
+    ///
+    /// Can be changed at any time, interfaces are fully rebuilt the next time they're updated.
     pub fn configure_defaults(font: Font, text_size: impl Into<iced_core::Pixels>) {
-        // TODO: Add ability to change after the global context has initialized.
-        let _ = crate::context::DEFAULTS.set((font, text_size.into()));
+        crate::context::set_defaults(font, text_size.into());
     }
 }
 
+pub mod clipboard {
+    //! Access the clipboard from a [`Task`](super::Task).
+    pub use iced_runtime::clipboard::{read, read_primary, write, write_primary};
+}
+
+pub mod task {
+    //! Create runtime tasks, run them with [`Interface::run_task`](crate::Interface::run_task).
+    pub use iced_runtime::task::{Handle, Task};
+
+    pub use crate::executor::{poll_each_frame, EachFrame};
+}
+
 #[allow(hidden_glob_reexports)]
 pub mod widget {
     //! Use the built-in widgets or create your own.
@@ -88,3 +283,112 @@ pub mod advanced {
 
 pub type Renderer = iced_tiny_skia::Renderer<iced_tiny_skia::color_profile::RGBA>;
 pub type Element<'a, Message, Theme> = iced_core::Element<'a, Message, Theme, Renderer>;
+
+#[cfg(test)]
+mod tests {
+    use super::font::{self, Font};
+    use super::widget::text;
+    use super::widget::text::Shaping;
+    use super::Size;
+    use crate::Interface;
+
+    use iced_core::text::{LineHeight, Paragraph as _, Text, Wrapping};
+    use iced_core::{alignment, Pixels};
+    use iced_graphics::text::cosmic_text::{self, Attrs, Buffer, Metrics};
+    use iced_graphics::text::Paragraph;
+
+    /// The family of the face picked for U+E000 in text of the default font.
+    fn fallback_family() -> String {
+        let mut font_system = iced_graphics::text::font_system().write().unwrap();
+        let font_system = font_system.raw();
+
+        let mut buffer = Buffer::new(font_system, Metrics::new(16.0, 20.0));
+        buffer.set_text(
+            font_system,
+            "\u{E000}",
+            Attrs::new(),
+            cosmic_text::Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(font_system, false);
+
+        let glyph = buffer.layout_runs().flat_map(|run| run.glyphs).next();
+        let face = font_system.db().face(glyph.unwrap().font_id).unwrap();
+        face.families[0].0.clone()
+    }
+
+    /// The fonts are made for this test, each has a single glyph at U+E000 where platform fonts
+    /// have none, a square in one and a triangle in the other.
+    #[test]
+    fn last_fallbacks_are_tried_in_order() {
+        let _lock = font::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let square =
+            font::load_bytes(include_bytes!("../tests/fonts/FallbackSquare.ttf").as_slice());
+        let triangle =
+            font::load_bytes(include_bytes!("../tests/fonts/FallbackTriangle.ttf").as_slice());
+        let (square, triangle) = (square.unwrap(), triangle.unwrap());
+
+        let render = || {
+            let ui = text("\u{E000}").size(20).shaping(Shaping::Advanced);
+            let ui = ui.into();
+            Interface::<()>::new().render_image(Size::new(24.0, 24.0), 1.0, ui)
+        };
+
+        // Without fallbacks, the font loaded first has the glyph.
+        let unordered = render();
+        assert_eq!(fallback_family(), square);
+
+        font::set_last_fallbacks([triangle, square]);
+        assert_eq!(fallback_family(), triangle);
+        assert_ne!(render().bytes, unordered.bytes);
+
+        font::set_last_fallbacks([square, triangle]);
+        assert_eq!(fallback_family(), square);
+        assert_eq!(render().bytes, unordered.bytes);
+    }
+
+    /// Latin in a bundled font, with Hebrew from a fallback like a player name in chat.
+    #[test]
+    fn mixed_scripts_have_no_missing_glyphs() {
+        let _lock = font::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let latin = font::load_bytes(include_bytes!("../tests/fonts/Tuffy.ttf").as_slice());
+        let hebrew =
+            font::load_bytes(include_bytes!("../tests/fonts/FallbackHebrew.ttf").as_slice());
+        font::set_last_fallbacks([hebrew.unwrap()]);
+
+        // Shaped as the renderer shapes the content of a text widget.
+        let content = "Hello שלום!";
+        let paragraph = Paragraph::with_text(Text {
+            content,
+            bounds: Size::INFINITY,
+            size: Pixels(20.0),
+            line_height: LineHeight::default(),
+            // Tuffy is medium weight, any other picks a fallback for the Latin too.
+            font: Font {
+                weight: font::Weight::Medium,
+                ..Font::with_name(latin.unwrap())
+            },
+            horizontal_alignment: alignment::Horizontal::Left,
+            vertical_alignment: alignment::Vertical::Top,
+            shaping: Shaping::Advanced,
+            wrapping: Wrapping::None,
+        });
+
+        let glyphs: Vec<_> = paragraph
+            .buffer()
+            .layout_runs()
+            .flat_map(|run| run.glyphs)
+            .collect();
+        assert_eq!(glyphs.len(), content.chars().count());
+        for glyph in glyphs {
+            let character = &content[glyph.start..glyph.end];
+            assert_ne!(glyph.glyph_id, 0, "{character:?} is missing");
+        }
+    }
+
+    #[test]
+    fn rejects_data_that_is_not_a_font() {
+        let result = font::load_bytes(b"definitely not a font".as_slice());
+        assert!(matches!(result, Err(font::Error::Invalid)));
+    }
+}
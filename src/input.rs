@@ -0,0 +1,177 @@
+//! UI aware versions of [`macroquad::input`].
+//!
+//! These behave like their macroquad counterparts, except they report no input when the
+//! UI consumed it during the last frame. Swap `use macroquad::input::*` for
+//! `use iced_macroquad::input::*` to stop gameplay code reacting to clicks meant for the UI.
+//!
+//! macroquad doesn't count frames, so a coroutine started on first use counts them. It runs with
+//! the game's own coroutines, and [`stop_all_coroutines`] stops it too, say on a scene change.
+//! It's restarted the next time the frame is needed, but the count can then lag by one, so what
+//! the UI consumed may be forgotten a frame early.
+//!
+//! [`stop_all_coroutines`]: macroquad::experimental::coroutines::stop_all_coroutines
+
+use std::collections::HashSet;
+
+pub use macroquad::input::*;
+
+use crate::context::{self, global};
+use crate::interface::Report;
+
+/// Input consumed by every [`Interface`](crate::Interface) viewed during a frame.
+#[derive(Debug, Default)]
+pub(crate) struct Capture {
+    frame: u64,
+    mouse: bool,
+    keyboard: bool,
+}
+
+impl Capture {
+    /// Record what an interface consumed, forgetting what was consumed in earlier frames.
+    pub fn record(&mut self, frame: u64, report: &Report, hovered: bool) {
+        if self.frame != frame {
+            *self = Self {
+                frame,
+                ..Self::default()
+            };
+        }
+
+        self.mouse |= report.mouse_captured || hovered;
+        self.keyboard |= report.keyboard_captured || report.text_focused;
+    }
+
+    /// Captures older than the last frame belong to interfaces that are no longer shown.
+    fn is_recent(&self, frame: u64) -> bool {
+        self.frame + 1 >= frame
+    }
+
+    pub fn mouse(&self, frame: u64) -> bool {
+        self.mouse && self.is_recent(frame)
+    }
+
+    pub fn keyboard(&self, frame: u64) -> bool {
+        self.keyboard && self.is_recent(frame)
+    }
+}
+
+/// Returns true if the UI consumed mouse or touch input during the last frame.
+pub fn is_mouse_captured() -> bool {
+    global::iced_ctx_mut(|ctx| ctx.capture.mouse(context::frame()))
+}
+
+/// Returns true if the UI consumed keyboard input during the last frame.
+pub fn is_keyboard_captured() -> bool {
+    global::iced_ctx_mut(|ctx| ctx.capture.keyboard(context::frame()))
+}
+
+pub fn touches() -> Vec<Touch> {
+    match is_mouse_captured() {
+        true => Vec::new(),
+        false => macroquad::input::touches(),
+    }
+}
+
+pub fn touches_local() -> Vec<Touch> {
+    match is_mouse_captured() {
+        true => Vec::new(),
+        false => macroquad::input::touches_local(),
+    }
+}
+
+pub fn mouse_wheel() -> (f32, f32) {
+    match is_mouse_captured() {
+        true => (0.0, 0.0),
+        false => macroquad::input::mouse_wheel(),
+    }
+}
+
+/// Detect if the button is being pressed
+pub fn is_mouse_button_down(btn: MouseButton) -> bool {
+    !is_mouse_captured() && macroquad::input::is_mouse_button_down(btn)
+}
+
+/// Detect if the button has been pressed once
+pub fn is_mouse_button_pressed(btn: MouseButton) -> bool {
+    !is_mouse_captured() && macroquad::input::is_mouse_button_pressed(btn)
+}
+
+/// Detect if the button has been released this frame
+pub fn is_mouse_button_released(btn: MouseButton) -> bool {
+    !is_mouse_captured() && macroquad::input::is_mouse_button_released(btn)
+}
+
+/// Detect if the key has been pressed once
+pub fn is_key_pressed(key_code: KeyCode) -> bool {
+    !is_keyboard_captured() && macroquad::input::is_key_pressed(key_code)
+}
+
+/// Detect if the key is being pressed
+pub fn is_key_down(key_code: KeyCode) -> bool {
+    !is_keyboard_captured() && macroquad::input::is_key_down(key_code)
+}
+
+/// Detect if the key has been released this frame
+pub fn is_key_released(key_code: KeyCode) -> bool {
+    !is_keyboard_captured() && macroquad::input::is_key_released(key_code)
+}
+
+/// Return the last pressed char.
+/// Each "get_char_pressed" call will consume a character from the input queue,
+/// even if the UI consumed it.
+pub fn get_char_pressed() -> Option<char> {
+    let char = macroquad::input::get_char_pressed();
+    char.filter(|_| !is_keyboard_captured())
+}
+
+/// Return the last pressed key.
+pub fn get_last_key_pressed() -> Option<KeyCode> {
+    match is_keyboard_captured() {
+        true => None,
+        false => macroquad::input::get_last_key_pressed(),
+    }
+}
+
+pub fn get_keys_pressed() -> HashSet<KeyCode> {
+    match is_keyboard_captured() {
+        true => HashSet::new(),
+        false => macroquad::input::get_keys_pressed(),
+    }
+}
+
+pub fn get_keys_down() -> HashSet<KeyCode> {
+    match is_keyboard_captured() {
+        true => HashSet::new(),
+        false => macroquad::input::get_keys_down(),
+    }
+}
+
+pub fn get_keys_released() -> HashSet<KeyCode> {
+    match is_keyboard_captured() {
+        true => HashSet::new(),
+        false => macroquad::input::get_keys_released(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_last_until_the_frame_after() {
+        let mut capture = Capture::default();
+        let typing = Report {
+            text_focused: true,
+            ..Report::default()
+        };
+
+        capture.record(5, &Report::default(), true);
+        capture.record(5, &typing, false);
+        assert!(capture.mouse(5) && capture.keyboard(5));
+        assert!(capture.mouse(6) && capture.keyboard(6));
+        assert!(!capture.mouse(7) && !capture.keyboard(7));
+
+        // A new frame forgets what the last one consumed.
+        capture.record(6, &Report::default(), false);
+        assert!(!capture.mouse(6) && !capture.keyboard(6));
+    }
+}
@@ -1,21 +1,92 @@
 use std::marker::PhantomData;
 
+use iced_core::event::Status;
 use iced_core::mouse::{Cursor, Interaction};
 use iced_core::renderer::Style;
-use iced_core::{Element, Point};
-use iced_runtime::{user_interface::Cache, UserInterface};
+use iced_core::time::Instant;
+use iced_core::widget::operation::{Focusable, Operation};
+use iced_core::widget::Id;
+use iced_core::window::{self, RedrawRequest};
+use iced_core::{mouse, touch, Element, Event, Point, Rectangle, Size, Vector};
+use iced_graphics::Viewport;
+use iced_runtime::user_interface::{Cache, State};
+use iced_runtime::{Task, UserInterface};
+use macroquad::texture::{Image, Texture2D};
 
 use crate::iced::Renderer;
 use crate::mq::CursorIcon;
 
-use crate::context::{global, Context};
+use crate::clipboard::Clipboard;
+use crate::context::renderer::Compositor;
+use crate::context::{create_renderer, frame, global, Context};
 use crate::convert;
+use crate::executor::Executor;
+
+/// How the UI responded to the input it received during a frame.
+///
+/// Use this to stop the game from reacting to input meant for the UI.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// A mouse or touch event was handled by a widget.
+    pub mouse_captured: bool,
+    /// A keyboard event was handled by a widget.
+    pub keyboard_captured: bool,
+    /// A text field has focus and will consume typed text.
+    pub text_focused: bool,
+    /// Events that no widget handled.
+    pub ignored_events: Vec<Event>,
+    /// When a widget wants the UI updated again, e.g. to animate or blink a caret.
+    pub redraw_request: Option<RedrawRequest>,
+}
+
+impl Report {
+    fn new(events: &[Event], statuses: &[Status], text_focused: bool) -> Self {
+        let mut report = Self {
+            text_focused,
+            ..Self::default()
+        };
+
+        for (event, status) in events.iter().zip(statuses) {
+            match (event, status) {
+                (Event::Mouse(_) | Event::Touch(_), Status::Captured) => {
+                    report.mouse_captured = true
+                }
+                (Event::Keyboard(_), Status::Captured) => report.keyboard_captured = true,
+                // Sent by us rather than the window, so there's nothing for the game to handle.
+                (Event::Window(window::Event::RedrawRequested(_)), _) => (),
+                (_, Status::Captured) => (),
+                (event, Status::Ignored) => report.ignored_events.push(event.clone()),
+            }
+        }
+
+        report
+    }
+}
 
 pub struct Interface<Message, Theme = iced_core::Theme> {
+    frame_events: Vec<(usize, Event)>,
+    /// The last frame this interface was given input for.
+    events_received: Option<u64>,
     in_events: Vec<iced_core::Event>,
+    /// Frame event ids of `in_events`.
+    in_ids: Vec<usize>,
     ui_cache: Option<Cache>,
+    compositor: Option<Compositor>,
+    placement: Option<Rectangle>,
     theme: Theme,
+    clipboard: Option<Box<dyn Clipboard>>,
+    executor: Executor<Message>,
     interacted: bool,
+    redraw_on_demand: bool,
+    /// The UI has to be rebuilt, even if no input arrived.
+    outdated: bool,
+    redraw_request: Option<RedrawRequest>,
+    /// Physical size and scale factor of the last render.
+    rendered_at: Option<(Size<u32>, f64)>,
+    hovered: bool,
+    text_focused: bool,
+    /// Generation of the default font and text size the UI was laid out with.
+    defaults: Option<usize>,
     _message: PhantomData<Message>,
 }
 
@@ -28,34 +99,328 @@ impl<Message, Theme: Default> Interface<Message, Theme> {
 impl<Message, Theme> Interface<Message, Theme> {
     pub fn new_themed(theme: Theme) -> Self {
         Self {
+            frame_events: Vec::new(),
+            events_received: None,
             in_events: Vec::new(),
+            in_ids: Vec::new(),
             ui_cache: None,
+            compositor: None,
+            placement: None,
             theme,
+            clipboard: None,
+            executor: Executor::new(),
             interacted: false,
+            redraw_on_demand: false,
+            outdated: true,
+            redraw_request: None,
+            rendered_at: None,
+            hovered: false,
+            text_focused: false,
+            defaults: None,
             _message: PhantomData,
         }
     }
 
     pub fn set_theme(&mut self, theme: Theme) {
-        self.theme = theme
+        self.theme = theme;
+        self.outdated = true;
+    }
+
+    /// Use `clipboard` for this interface, instead of the one set with
+    /// [`clipboard::set_clipboard`](crate::clipboard::set_clipboard).
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        self.clipboard = Some(Box::new(clipboard));
+    }
+
+    /// Run `task` alongside the UI, usually one returned from handling a message.
+    ///
+    /// Tasks are polled on every update, from the game loop rather than other threads. The messages
+    /// they produce are pushed with those of the UI, and their widget operations and clipboard
+    /// actions run against this interface. Window and system actions are ignored.
+    ///
+    /// Wrap macroquad futures with [`poll_each_frame`](crate::iced::task::poll_each_frame) before
+    /// batching them with other tasks.
+    pub fn run_task(&mut self, task: Task<Message>) {
+        self.executor.spawn(task);
+    }
+
+    /// Only rebuild and render the UI when something changed, otherwise the last frame is reused.
+    ///
+    /// The UI is rebuilt on input, when a widget asks for a redraw, when it produced messages or
+    /// after [`Interface::request_redraw`]. Call that whenever the game changes state shown by
+    /// the UI without going through its messages.
+    pub fn set_redraw_on_demand(&mut self, redraw_on_demand: bool) {
+        self.redraw_on_demand = redraw_on_demand;
+    }
+
+    /// Rebuild the UI the next time it's updated, see [`Interface::set_redraw_on_demand`].
+    pub fn request_redraw(&mut self) {
+        self.outdated = true;
+    }
+
+    /// Checks if the UI has to be rebuilt, regardless of input.
+    ///
+    /// When this is false, the game loop can wait for input or [`Interface::next_redraw_at`].
+    pub fn needs_redraw(&self) -> bool {
+        self.next_redraw_at().is_some_and(|at| at <= Instant::now())
+    }
+
+    /// When the UI has to be rebuilt next, regardless of input.
+    pub fn next_redraw_at(&self) -> Option<Instant> {
+        if self.outdated {
+            return Some(Instant::now());
+        }
+
+        match self.redraw_request? {
+            RedrawRequest::NextFrame => Some(Instant::now()),
+            RedrawRequest::At(at) => Some(at),
+        }
     }
 
     /// Interact with, and view the UI. All interactions will be pushed to messages.
+    ///
+    /// Same as calling [`Interface::update`] followed by [`Interface::draw`].
+    ///
+    /// Returns a [`Report`] of what input the UI consumed this frame.
     pub fn view<'a>(
         &mut self,
         messages: &mut Vec<Message>,
         ui: Element<'a, Message, Theme, Renderer>,
-    ) {
-        global::iced_ctx_mut(|ctx| self.present(ctx, messages, ui.into()));
+    ) -> Report {
+        let report = self.update(messages, ui);
+        self.draw();
+        report
+    }
+
+    /// Like [`Interface::view`], but the UI is laid out and drawn inside `bounds`.
+    ///
+    /// `bounds` is in logical screen pixels. Presses and touches outside of it are ignored.
+    pub fn view_in<'a>(
+        &mut self,
+        bounds: Rectangle,
+        messages: &mut Vec<Message>,
+        ui: Element<'a, Message, Theme, Renderer>,
+    ) -> Report {
+        let report = self.update_in(bounds, messages, ui);
+        self.draw();
+        report
+    }
+
+    /// Like [`Interface::view`], but the UI is rendered into `texture` instead of the screen.
+    ///
+    /// Each pixel of the texture is one logical pixel of the UI, use the `texture` of a
+    /// `RenderTarget` to draw it on anything in the game world. The texture holds premultiplied
+    /// alpha, so draw it with [`material::premultiplied_alpha`](crate::material::premultiplied_alpha).
+    ///
+    /// `cursor` maps a position on the screen, in logical pixels, to a pixel of the texture.
+    /// Return `None` if the position doesn't land on the texture.
+    pub fn view_to_texture<'a>(
+        &mut self,
+        texture: &Texture2D,
+        cursor: impl Fn(Point) -> Option<Point>,
+        messages: &mut Vec<Message>,
+        ui: Element<'a, Message, Theme, Renderer>,
+    ) -> Report {
+        let size = texture.size();
+        let viewport = Viewport::with_physical_size(Size::new(size.x as u32, size.y as u32), 1.0);
+
+        let report =
+            global::iced_ctx_mut(|ctx| self.present(ctx, &viewport, &cursor, messages, ui));
+        self.placement = None;
+
+        if let Some(compositor) = &mut self.compositor {
+            compositor.upload_to(texture);
+        }
+
+        report
+    }
+
+    /// Let the UI handle this frame's input, and render it without drawing it yet.
+    ///
+    /// All interactions will be pushed to messages. Call [`Interface::draw`] to put the UI on
+    /// the screen, e.g. after the game has been drawn, so the game can first react to the
+    /// returned [`Report`].
+    ///
+    /// Every interface updated during a frame is given its input. Update the topmost one first,
+    /// input it captures isn't given to the ones updated after it. Updating the same interface
+    /// again in a frame gives it no input, so presses aren't handled twice.
+    pub fn update<'a>(
+        &mut self,
+        messages: &mut Vec<Message>,
+        ui: Element<'a, Message, Theme, Renderer>,
+    ) -> Report {
+        global::iced_ctx_mut(|ctx| {
+            let viewport = ctx.viewport();
+            let report = self.present(ctx, &viewport, &Some, messages, ui);
+            self.placement = Some(Rectangle::with_size(viewport.logical_size()));
+            report
+        })
+    }
+
+    /// Like [`Interface::update`], but the UI is laid out inside `bounds`.
+    ///
+    /// `bounds` is in logical screen pixels. Presses and touches outside of it are ignored.
+    pub fn update_in<'a>(
+        &mut self,
+        bounds: Rectangle,
+        messages: &mut Vec<Message>,
+        ui: Element<'a, Message, Theme, Renderer>,
+    ) -> Report {
+        global::iced_ctx_mut(|ctx| {
+            let viewport = ctx.viewport_for(bounds.size());
+            let offset = Vector::new(bounds.x, bounds.y);
+            let report = self.present(ctx, &viewport, &|p| Some(p - offset), messages, ui);
+            self.placement = Some(bounds);
+            report
+        })
+    }
+
+    /// Draw the UI as of the last [`Interface::update`] on the screen.
+    ///
+    /// It can be drawn any number of times per frame, or not at all. Does nothing if the UI
+    /// hasn't been updated yet, or was last rendered into a texture.
+    ///
+    /// The camera is restored afterwards, but the material is reset to macroquad's default, as
+    /// macroquad can't tell which one was in use. Set it again if the game draws more after this.
+    pub fn draw(&mut self) {
+        if let (Some(compositor), Some(placement)) = (&mut self.compositor, self.placement) {
+            compositor.draw_texture(placement.position(), placement.size());
+        }
+    }
+
+    /// A screenshot of the UI as of the last update, in RGBA with straight alpha.
+    ///
+    /// Returns `None` if the UI hasn't been rendered yet.
+    pub fn screenshot(&self) -> Option<Image> {
+        self.compositor.as_ref().map(Compositor::to_image)
+    }
+
+    /// A screenshot of the whole screen, with the UI as of the last update on top.
+    ///
+    /// Call it after the game is drawn, but before [`Interface::draw`], or the UI is included
+    /// twice. Like [`Interface::draw`] it leaves out UI rendered into a texture.
+    pub fn screenshot_with_game(&self) -> Image {
+        let mut screen = macroquad::texture::get_screen_data();
+
+        // OpenGL reads the screen bottom up.
+        let row = screen.width as usize * 4;
+        let rows: Vec<_> = screen
+            .bytes
+            .chunks_exact(row)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        screen.bytes = rows;
+
+        if let (Some(compositor), Some(placement)) = (&self.compositor, self.placement) {
+            compositor.blend_onto(&mut screen, placement.position());
+        }
+
+        screen
+    }
+
+    /// Render the UI into an image, without a window or any input.
+    ///
+    /// The image is `logical_size` scaled by `scale_factor`, in RGBA with straight alpha. Useful
+    /// for tests and screenshots, as it doesn't need macroquad to be running.
+    pub fn render_image<'a>(
+        &mut self,
+        logical_size: Size,
+        scale_factor: f64,
+        ui: Element<'a, Message, Theme, Renderer>,
+    ) -> Image {
+        let scale = scale_factor as f32;
+        let physical_size = Size::new(
+            (logical_size.width * scale).round() as u32,
+            (logical_size.height * scale).round() as u32,
+        );
+        let viewport = Viewport::with_physical_size(physical_size, scale_factor);
+
+        // The global context needs a window, so use a renderer of our own.
+        let mut renderer = create_renderer();
+        let mut interface = UserInterface::build(
+            ui,
+            viewport.logical_size(),
+            self.ui_cache.take().unwrap_or_default(),
+            &mut renderer,
+        );
+        interface.draw(
+            &mut renderer,
+            &self.theme,
+            &Style::default(),
+            Cursor::Unavailable,
+        );
+        self.ui_cache = Some(interface.into_cache());
+
+        let mut compositor = Compositor::new(physical_size);
+        compositor.render(&mut renderer, &viewport);
+        compositor.to_image()
     }
 
     fn present(
         &mut self,
         ctx: &mut Context,
+        viewport: &Viewport,
+        map_cursor: &dyn Fn(Point) -> Option<Point>,
         messages: &mut Vec<Message>,
         ui: Element<'_, Message, Theme, Renderer>,
-    ) {
-        let viewport = ctx.viewport();
+    ) -> Report {
+        // Lay out and render everything again with new defaults.
+        let defaults = ctx.update_defaults();
+        if self.defaults.replace(defaults) != Some(defaults) {
+            self.ui_cache = None;
+            self.outdated = true;
+            if let Some(compositor) = &mut self.compositor {
+                compositor.invalidate();
+            }
+        }
+
+        // Fetch the external inputs that no interface above this one captured.
+        self.frame_events.clear();
+        ctx.frame_events(&mut self.events_received, &mut self.frame_events);
+
+        // Move the inputs into the space of the interface.
+        let bounds = Rectangle::with_size(viewport.logical_size());
+        let map = |position| map_cursor(position).filter(|p| bounds.contains(*p));
+        let cursor = map_cursor(Point::from(ctx.mouse_position()));
+        let cursor_inside = cursor.is_some_and(|p| bounds.contains(p));
+        self.frame_events
+            .retain_mut(|(_, event)| translate(event, map_cursor, &map, cursor_inside));
+        (self.in_ids, self.in_events) = self.frame_events.drain(..).unzip();
+
+        // Let the tasks make progress, their messages change the UI like those of widgets.
+        let produced = messages.len();
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard.as_mut(),
+            None => ctx.clipboard.as_mut(),
+        };
+        if self.executor.poll(clipboard, messages) {
+            self.outdated = true;
+        }
+
+        // Reuse the last frame if nothing changed.
+        let size = (viewport.physical_size(), viewport.scale_factor());
+        if self.redraw_on_demand
+            && self.in_events.is_empty()
+            && !self.needs_redraw()
+            && self.rendered_at == Some(size)
+            && self.ui_cache.is_some()
+        {
+            let report = Report {
+                text_focused: self.text_focused,
+                redraw_request: self.redraw_request,
+                ..Report::default()
+            };
+            ctx.capture.record(frame(), &report, self.hovered);
+            return report;
+        }
+
+        // Let widgets animate, their redraw requests are answered the next time this is called.
+        self.in_events.push(Event::Window(
+            window::Event::RedrawRequested(Instant::now()),
+        ));
+
         // Build the interface.
         let mut interface = UserInterface::build(
             ui,
@@ -64,23 +429,51 @@ impl<Message, Theme> Interface<Message, Theme> {
             &mut ctx.renderer,
         );
 
-        // Fetch all external inputs.
-        self.in_events.clear();
-        ctx.read_events(&mut self.in_events);
-
         // Update the interface by processing the events.
-        let cursor = Cursor::Available(Point::from(ctx.mouse_position()));
-        let (_, _statuses) = interface.update(
+        let cursor = match cursor.filter(|_| cursor_inside) {
+            Some(position) => Cursor::Available(position),
+            None => Cursor::Unavailable,
+        };
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard.as_mut(),
+            None => ctx.clipboard.as_mut(),
+        };
+        let (state, statuses) = interface.update(
             &self.in_events,
             cursor,
             &mut ctx.renderer,
-            &mut ctx.clipboard,
+            clipboard,
             messages,
         );
 
+        // Messages change the state of the game, which the next UI is built from.
+        self.outdated = messages.len() > produced;
+        match state {
+            State::Outdated => self.outdated = true,
+            State::Updated { redraw_request } => self.redraw_request = redraw_request,
+        }
+
+        for (id, status) in self.in_ids.iter().zip(&statuses) {
+            if *status == Status::Captured {
+                ctx.capture_event(*id);
+            }
+        }
+
+        // Operations from tasks, like focusing a text input.
+        self.executor.operate(&mut interface, &ctx.renderer);
+
+        let mut focus = FindFocus::default();
+        interface.operate(&ctx.renderer, &mut focus);
+        let mut report = Report::new(&self.in_events, &statuses, focus.focused);
+        report.redraw_request = self.redraw_request;
+
         // Draw the interface onto the canvas.
         let interaction = interface.draw(&mut ctx.renderer, &self.theme, &Style::default(), cursor);
 
+        self.hovered = !matches!(interaction, Interaction::None | Interaction::Idle);
+        self.text_focused = report.text_focused;
+        ctx.capture.record(frame(), &report, self.hovered);
+
         // Update mouse cursor.
         if interaction == Interaction::None {
             if self.interacted {
@@ -95,8 +488,74 @@ impl<Message, Theme> Interface<Message, Theme> {
         // Cache the interface for reuse the next time view is called.
         self.ui_cache = Some(interface.into_cache());
 
-        // Render what's drawn on the canvas to the screen.
-        ctx.present(&viewport);
+        // Rasterize what's drawn on the canvas, it's shown once drawn or uploaded.
+        self.compositor
+            .get_or_insert_with(|| Compositor::new(viewport.physical_size()))
+            .render(&mut ctx.renderer, viewport);
+        self.rendered_at = Some(size);
+
+        report
+    }
+}
+
+/// Move a positional event into the space of the interface.
+///
+/// `map` only succeeds for positions on the interface, while `map_any` may also succeed for
+/// positions around it. Returns false for presses that miss the interface. Anything already
+/// underway is kept if possible, so drags and releases still reach the interface.
+fn translate(
+    event: &mut Event,
+    map_any: &dyn Fn(Point) -> Option<Point>,
+    map: &dyn Fn(Point) -> Option<Point>,
+    cursor_inside: bool,
+) -> bool {
+    match event {
+        Event::Mouse(mouse::Event::ButtonPressed(_) | mouse::Event::WheelScrolled { .. }) => {
+            cursor_inside
+        }
+        Event::Touch(touch::Event::FingerPressed { position, .. }) => match map(*position) {
+            Some(mapped) => {
+                *position = mapped;
+                true
+            }
+            None => false,
+        },
+        Event::Mouse(mouse::Event::CursorMoved { position })
+        | Event::Touch(
+            touch::Event::FingerMoved { position, .. }
+            | touch::Event::FingerLifted { position, .. }
+            | touch::Event::FingerLost { position, .. },
+        ) => match map_any(*position) {
+            Some(mapped) => {
+                *position = mapped;
+                true
+            }
+            None => false,
+        },
+        _ => true,
+    }
+}
+
+/// Checks if any focusable widget, i.e. a text field, has focus.
+#[derive(Default)]
+struct FindFocus {
+    focused: bool,
+}
+
+impl Operation for FindFocus {
+    fn container(
+        &mut self,
+        _id: Option<&Id>,
+        _bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation),
+    ) {
+        if !self.focused {
+            operate_on_children(self)
+        }
+    }
+
+    fn focusable(&mut self, state: &mut dyn Focusable, _id: Option<&Id>) {
+        self.focused |= state.is_focused();
     }
 }
 
@@ -104,9 +563,33 @@ impl<Message, Theme> Drop for Interface<Message, Theme> {
     fn drop(&mut self) {
         // Interface may be dropped before we can reset the mouse icon.
         if self.interacted {
-            global::iced_ctx_mut(|ctx| {
+            global::try_iced_ctx_mut(|ctx| {
                 ctx.set_mouse_icon(CursorIcon::Default);
-            })
+            });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced_core::{Color, Length};
+    use iced_widget::{container, horizontal_space, row};
+
+    #[test]
+    fn renders_without_a_window() {
+        let mut interface = Interface::<()>::new();
+        let left = container(horizontal_space())
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(|_| container::background(Color::from_rgba8(255, 0, 255, 0.5)));
+        let ui = row![left, horizontal_space()].into();
+
+        let image = interface.render_image(Size::new(20.0, 10.0), 2.0, ui);
+
+        assert_eq!((image.width, image.height), (40, 20));
+        let pixel = |x: usize, y: usize| &image.bytes[(y * 40 + x) * 4..][..4];
+        assert_eq!(pixel(5, 5), [255, 0, 255, 128]);
+        assert_eq!(pixel(35, 5), [0, 0, 0, 0]);
+    }
+}
@@ -1,10 +1,18 @@
+pub mod clipboard;
 mod context;
 mod convert;
 mod event_handler;
+mod executor;
 mod interface;
+mod program;
 pub mod iced;
+pub mod input;
+pub mod material;
+pub mod snapshot;
 
 use macroquad;
 use macroquad::miniquad as mq;
 
-pub use interface::Interface;
\ No newline at end of file
+pub use context::global::{init, shutdown};
+pub use interface::{Interface, Report};
+pub use program::{run, Program};
\ No newline at end of file
@@ -0,0 +1,237 @@
+//! Draw the premultiplied pixels produced by tiny-skia.
+
+use std::cell::OnceCell;
+
+use macroquad::material::{load_material, Material, MaterialParams};
+
+use crate::mq::{BlendFactor, BlendState, BlendValue, Equation, PipelineParams, ShaderSource};
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}"#;
+
+const FRAGMENT: &str = r#"#version 100
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+
+void main() {
+    gl_FragColor = color * texture2D(Texture, uv);
+}"#;
+
+const METAL: &str = r#"#include <metal_stdlib>
+using namespace metal;
+
+struct Uniforms
+{
+    float4x4 Model;
+    float4x4 Projection;
+};
+
+struct Vertex
+{
+    float3 position    [[attribute(0)]];
+    float2 texcoord    [[attribute(1)]];
+    float4 color0      [[attribute(2)]];
+};
+
+struct RasterizerData
+{
+    float4 position [[position]];
+    float4 color [[user(locn0)]];
+    float2 uv [[user(locn1)]];
+};
+
+vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+{
+    RasterizerData out;
+
+    out.position = uniforms.Model * uniforms.Projection * float4(v.position, 1);
+    out.color = v.color0 / 255.0;
+    out.uv = v.texcoord;
+
+    return out;
+}
+
+fragment float4 fragmentShader(RasterizerData in [[stage_in]], texture2d<float> tex [[texture(0)]], sampler texSmplr [[sampler(0)]])
+{
+    return in.color * tex.sample(texSmplr, in.uv);
+}"#;
+
+/// A material that blends textures holding premultiplied alpha, such as the UI surface.
+///
+/// Textures filled by [`Interface::view_to_texture`](crate::Interface::view_to_texture)
+/// should be drawn with it too, otherwise translucent edges come out too dark.
+pub fn premultiplied_alpha() -> Material {
+    thread_local! {
+        static MATERIAL: OnceCell<Material> = const { OnceCell::new() };
+    }
+
+    MATERIAL.with(|material| material.get_or_init(load).clone())
+}
+
+fn pipeline_params() -> PipelineParams {
+    // The colour has already been multiplied by alpha, so it's added as is.
+    let blend = BlendState::new(
+        Equation::Add,
+        BlendFactor::One,
+        BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+    );
+
+    PipelineParams {
+        color_blend: Some(blend),
+        alpha_blend: Some(blend),
+        ..Default::default()
+    }
+}
+
+fn load() -> Material {
+    let backend = unsafe { macroquad::window::get_internal_gl() }
+        .quad_context
+        .info()
+        .backend;
+
+    let shader = match backend {
+        crate::mq::Backend::OpenGl => ShaderSource::Glsl {
+            vertex: VERTEX,
+            fragment: FRAGMENT,
+        },
+        crate::mq::Backend::Metal => ShaderSource::Msl { program: METAL },
+    };
+
+    load_material(
+        shader,
+        MaterialParams {
+            pipeline_params: pipeline_params(),
+            ..Default::default()
+        },
+    )
+    .expect("Load premultiplied alpha material")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Rgba = [f32; 4];
+
+    const FACTORS: [BlendFactor; 4] = [
+        BlendFactor::Zero,
+        BlendFactor::One,
+        BlendFactor::Value(BlendValue::SourceAlpha),
+        BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+    ];
+
+    /// What the GPU does with a blend factor.
+    fn factor(factor: BlendFactor, src: Rgba) -> f32 {
+        match factor {
+            BlendFactor::Zero => 0.0,
+            BlendFactor::One => 1.0,
+            BlendFactor::Value(BlendValue::SourceAlpha) => src[3],
+            BlendFactor::OneMinusValue(BlendValue::SourceAlpha) => 1.0 - src[3],
+            _ => panic!("{factor:?} isn't used by the blend states under test"),
+        }
+    }
+
+    fn blend(src: Rgba, dst: Rgba, sfactor: BlendFactor, dfactor: BlendFactor) -> Rgba {
+        let (s, d) = (factor(sfactor, src), factor(dfactor, src));
+        std::array::from_fn(|i| src[i] * s + dst[i] * d)
+    }
+
+    /// The factors of an additive blend state, which miniquad keeps private.
+    fn factors(state: BlendState) -> (BlendFactor, BlendFactor) {
+        FACTORS
+            .into_iter()
+            .flat_map(|s| FACTORS.map(|d| (s, d)))
+            .find(|&(s, d)| BlendState::new(Equation::Add, s, d) == state)
+            .unwrap_or_else(|| panic!("{state:?} isn't an additive blend the test can model"))
+    }
+
+    fn to_float(pixel: &[u8]) -> Rgba {
+        std::array::from_fn(|i| pixel[i] as f32 / 255.0)
+    }
+
+    /// A translucent, anti-aliased circle as tiny-skia would rasterize a UI panel.
+    fn render_ui() -> tiny_skia::Pixmap {
+        let mut pixmap = tiny_skia::Pixmap::new(32, 32).unwrap();
+        let mut paint = tiny_skia::Paint::default();
+        paint.set_color_rgba8(200, 40, 90, 150);
+        paint.anti_alias = true;
+
+        let circle = tiny_skia::PathBuilder::from_circle(16.0, 16.0, 12.5).unwrap();
+        pixmap.fill_path(
+            &circle,
+            &paint,
+            tiny_skia::FillRule::Winding,
+            tiny_skia::Transform::identity(),
+            None,
+        );
+        pixmap
+    }
+
+    #[test]
+    fn premultiplied_blend_matches_reference() {
+        let params = pipeline_params();
+        assert_eq!(params.color_blend, params.alpha_blend);
+        let (sfactor, dfactor) = factors(params.color_blend.expect("Blending is enabled"));
+
+        let game: Rgba = [0.2, 0.6, 0.9, 1.0];
+        let ui = render_ui();
+
+        let mut worst_straight_error = 0.0f32;
+
+        for pixel in ui.pixels() {
+            let premultiplied =
+                to_float(&[pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]);
+            let straight = pixel.demultiply();
+            let straight = to_float(&[
+                straight.red(),
+                straight.green(),
+                straight.blue(),
+                straight.alpha(),
+            ]);
+
+            // Source over, in straight alpha.
+            let alpha = straight[3];
+            let reference: Rgba = std::array::from_fn(|i| match i {
+                3 => alpha + game[3] * (1.0 - alpha),
+                _ => straight[i] * alpha + game[i] * (1.0 - alpha),
+            });
+
+            let output = blend(premultiplied, game, sfactor, dfactor);
+            for i in 0..4 {
+                // Allow for the rounding tiny-skia does when premultiplying.
+                assert!(
+                    (output[i] - reference[i]).abs() <= 2.0 / 255.0,
+                    "channel {i}: {output:?} != {reference:?}"
+                );
+            }
+
+            let straight_blend = blend(
+                premultiplied,
+                game,
+                BlendFactor::Value(BlendValue::SourceAlpha),
+                BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+            );
+            worst_straight_error =
+                worst_straight_error.max((straight_blend[0] - reference[0]).abs());
+        }
+
+        // Sanity check, blending as straight alpha gives the dark fringes.
+        assert!(worst_straight_error > 0.1);
+    }
+}
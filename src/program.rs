@@ -0,0 +1,52 @@
+use macroquad::color::WHITE;
+use macroquad::window::{clear_background, next_frame};
+
+use crate::iced::{Element, Task};
+use crate::Interface;
+
+/// An application made of a UI, and optionally a game drawn beneath it.
+///
+/// Pass it to [`run`] to drive it from `macroquad::main`.
+pub trait Program {
+    type Message;
+    type Theme: Default;
+
+    /// Handle a message produced by the UI or a task, returning more work to run.
+    fn update(&mut self, message: Self::Message) -> Task<Self::Message>;
+
+    /// The UI to show this frame.
+    fn view(&self) -> Element<'_, Self::Message, Self::Theme>;
+
+    fn theme(&self) -> Self::Theme {
+        Self::Theme::default()
+    }
+
+    /// Draw the game beneath the UI, clears the screen to white by default.
+    ///
+    /// Runs after the UI handled this frame's input, so [`crate::input`] tells what it captured.
+    fn draw_game(&mut self) {
+        clear_background(WHITE);
+    }
+}
+
+/// Run `program` forever, a frame at a time.
+///
+/// The UI is rebuilt every frame, as [`Program::draw_game`] may change the state it shows.
+pub async fn run<P: Program>(mut program: P) {
+    let mut interface = Interface::<P::Message, P::Theme>::new_themed(program.theme());
+    let mut messages = Vec::new();
+
+    loop {
+        for message in messages.drain(..) {
+            interface.run_task(program.update(message));
+        }
+
+        interface.set_theme(program.theme());
+        interface.update(&mut messages, program.view());
+
+        program.draw_game();
+        interface.draw();
+
+        next_frame().await
+    }
+}
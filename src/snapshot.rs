@@ -0,0 +1,317 @@
+//! Compare renders of a UI against reference images, to catch layouts breaking unnoticed.
+//!
+//! ```no_run
+//! # use iced_macroquad::iced::{widget::text, Element, Size, Theme};
+//! # use iced_macroquad::snapshot::Snapshot;
+//! let hud: Element<(), Theme> = text("Score: 0").into();
+//!
+//! Snapshot::new(Size::new(320.0, 240.0))
+//!     .tolerance(2)
+//!     .assert_eq("tests/snapshots/hud.png", hud);
+//! ```
+//!
+//! Run the tests with `ICED_MACROQUAD_UPDATE_SNAPSHOTS=1` to write the reference images.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use iced_core::{Element, Size};
+use macroquad::texture::Image;
+
+use crate::iced::Renderer;
+use crate::Interface;
+
+/// Set this environment variable to replace the reference images with new renders.
+pub const UPDATE_VAR: &str = "ICED_MACROQUAD_UPDATE_SNAPSHOTS";
+
+/// Renders a UI offscreen and compares it with a PNG.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    size: Size,
+    scale_factor: f64,
+    tolerance: u8,
+}
+
+impl Snapshot {
+    /// Render at `size` in logical pixels, with a scale factor of 1 and no tolerance.
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            scale_factor: 1.0,
+            tolerance: 0,
+        }
+    }
+
+    pub fn scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// How much each channel of a pixel may differ from the reference.
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Render `ui` and panic if it doesn't match the reference image at `path`.
+    pub fn assert_eq<'a, Message, Theme: Default>(
+        &self,
+        path: impl AsRef<Path>,
+        ui: Element<'a, Message, Theme, Renderer>,
+    ) {
+        let image =
+            Interface::<Message, Theme>::new().render_image(self.size, self.scale_factor, ui);
+        self.assert_image(path, &image);
+    }
+
+    /// Panic if `image` doesn't match the reference image at `path`.
+    pub fn assert_image(&self, path: impl AsRef<Path>, image: &Image) {
+        if let Err(error) = self.compare(path, image) {
+            panic!("{error}");
+        }
+    }
+
+    /// Compare `image` with the reference image at `path`.
+    ///
+    /// On a mismatch, the render and an image highlighting the differences are written next to
+    /// the reference. If [`UPDATE_VAR`] is set the reference is replaced instead.
+    pub fn compare(&self, path: impl AsRef<Path>, image: &Image) -> Result<(), Error> {
+        let update = std::env::var_os(UPDATE_VAR).is_some_and(|value| value != "0");
+        self.compare_or_update(path.as_ref(), image, update)
+    }
+
+    fn compare_or_update(&self, path: &Path, image: &Image, update: bool) -> Result<(), Error> {
+        if update {
+            return save(path, image);
+        }
+
+        let reference = match image::open(path) {
+            Ok(reference) => reference.to_rgba8(),
+            Err(image::ImageError::IoError(error))
+                if error.kind() == std::io::ErrorKind::NotFound =>
+            {
+                return Err(Error::MissingReference(path.to_owned()));
+            }
+            Err(error) => return Err(Error::Image(error)),
+        };
+
+        let actual = path.with_extension("actual.png");
+        let size = (image.width as u32, image.height as u32);
+
+        if reference.dimensions() != size {
+            save(&actual, image)?;
+            return Err(Error::SizeMismatch {
+                path: path.to_owned(),
+                expected: reference.dimensions(),
+                actual: size,
+            });
+        }
+
+        let mut diff = Vec::with_capacity(image.bytes.len());
+        let mut mismatched = 0;
+
+        for (expected, pixel) in reference
+            .as_raw()
+            .chunks_exact(4)
+            .zip(image.bytes.chunks_exact(4))
+        {
+            let differs = expected
+                .iter()
+                .zip(pixel)
+                .any(|(a, b)| a.abs_diff(*b) > self.tolerance);
+
+            if differs {
+                mismatched += 1;
+                diff.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                // A faded copy of the reference, so the differences can be located.
+                let luma = (expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 3;
+                let faded = (luma * expected[3] as u32 / 255 / 4 + 191) as u8;
+                diff.extend_from_slice(&[faded, faded, faded, 255]);
+            }
+        }
+
+        if mismatched == 0 {
+            return Ok(());
+        }
+
+        let diff = Image {
+            bytes: diff,
+            width: image.width,
+            height: image.height,
+        };
+        save(&actual, image)?;
+        save(&path.with_extension("diff.png"), &diff)?;
+
+        Err(Error::PixelMismatch {
+            path: path.to_owned(),
+            pixels: mismatched,
+        })
+    }
+}
+
+/// Encode an image, such as a [screenshot](Interface::screenshot), as a PNG.
+pub fn encode_png(image: &Image) -> Result<Vec<u8>, image::ImageError> {
+    use image::ImageEncoder;
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png).write_image(
+        &image.bytes,
+        image.width as u32,
+        image.height as u32,
+        image::ColorType::Rgba8,
+    )?;
+    Ok(png)
+}
+
+fn save(path: &Path, image: &Image) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|error| Error::Image(error.into()))?;
+    }
+
+    image::save_buffer(
+        path,
+        &image.bytes,
+        image.width as u32,
+        image.height as u32,
+        image::ColorType::Rgba8,
+    )
+    .map_err(Error::Image)
+}
+
+/// Why a render didn't match its reference image.
+#[derive(Debug)]
+pub enum Error {
+    /// There is no reference image yet.
+    MissingReference(PathBuf),
+    /// The render and the reference image differ in size.
+    SizeMismatch {
+        path: PathBuf,
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// This many pixels differ by more than the tolerance.
+    PixelMismatch { path: PathBuf, pixels: usize },
+    /// Reading or writing an image failed.
+    Image(image::ImageError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingReference(path) => write!(
+                f,
+                "no reference image at {}, set {UPDATE_VAR}=1 to create it",
+                path.display()
+            ),
+            Error::SizeMismatch {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "render is {}x{} but {} is {}x{}",
+                actual.0,
+                actual.1,
+                path.display(),
+                expected.0,
+                expected.1
+            ),
+            Error::PixelMismatch { path, pixels } => write!(
+                f,
+                "{pixels} pixels differ from {}, see {}",
+                path.display(),
+                path.with_extension("diff.png").display()
+            ),
+            Error::Image(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::iced::font::{self, Font};
+    use crate::iced::widget::{column, container, horizontal_space, row, text, vertical_space};
+    use crate::iced::{Color, Length, Theme};
+
+    fn image(pixel: [u8; 4]) -> Image {
+        Image {
+            bytes: pixel.repeat(4 * 4),
+            width: 4,
+            height: 4,
+        }
+    }
+
+    #[test]
+    fn mismatches_write_a_diff() {
+        let dir =
+            std::env::temp_dir().join(format!("iced_macroquad_snapshot_{}", std::process::id()));
+        let path = dir.join("square.png");
+        let snapshot = Snapshot::new(Size::new(4.0, 4.0)).tolerance(2);
+
+        let reference = image([10, 20, 30, 255]);
+        assert!(matches!(
+            snapshot.compare_or_update(&path, &reference, false),
+            Err(Error::MissingReference(_))
+        ));
+
+        snapshot.compare_or_update(&path, &reference, true).unwrap();
+        snapshot
+            .compare_or_update(&path, &image([12, 18, 30, 255]), false)
+            .unwrap();
+
+        let result = snapshot.compare_or_update(&path, &image([13, 20, 30, 255]), false);
+        assert!(matches!(
+            result,
+            Err(Error::PixelMismatch { pixels: 16, .. })
+        ));
+        assert!(dir.join("square.diff.png").exists());
+        assert!(dir.join("square.actual.png").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// A HUD rendered with a bundled font, as system fonts differ between machines.
+    #[test]
+    fn hud_matches_reference() {
+        let _lock = font::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let family = font::load_bytes(include_bytes!("../tests/fonts/Tuffy.ttf").as_slice());
+        // Tuffy is medium weight, any other falls back to a system font.
+        let font = Font {
+            weight: font::Weight::Medium,
+            ..Font::with_name(family.unwrap())
+        };
+
+        // Red and blue differ in every colour, so swapped channels don't go unnoticed.
+        let label = |content| {
+            text(content)
+                .font(font)
+                .size(14)
+                .color(Color::from_rgb8(255, 220, 120))
+        };
+        let panel = |content| {
+            container(label(content))
+                .padding(6)
+                .style(|_| container::background(Color::from_rgb8(40, 50, 110)))
+        };
+        let hud = column![
+            row![panel("Score: 1250"), horizontal_space(), panel("Lives: 3"),],
+            vertical_space(),
+            container(horizontal_space())
+                .width(Length::Fill)
+                .height(12)
+                .style(|_| container::background(Color::from_rgb8(200, 60, 30))),
+        ]
+        .padding(8);
+
+        Snapshot::new(Size::new(240.0, 120.0))
+            .scale_factor(2.0)
+            .tolerance(2)
+            .assert_eq::<(), Theme>("tests/snapshots/hud.png", hud.into());
+    }
+}